@@ -1,8 +1,12 @@
 //! Utilities for interacting with the USOS API.
 
 pub mod auth;
+pub mod endpoint;
 pub mod errors;
+#[cfg(test)]
+mod forward_compatibility;
 pub mod oauth1;
 pub mod params;
+pub mod selector;
 pub mod types;
 pub mod util;
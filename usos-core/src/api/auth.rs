@@ -2,11 +2,13 @@
 
 use std::{
     collections::{HashMap, HashSet},
+    future::Future,
     io::Write,
     sync::Arc,
 };
 
 use anyhow::Context;
+use async_trait::async_trait;
 use secrecy::SecretString;
 
 use crate::{
@@ -39,6 +41,37 @@ pub struct AccessToken {
     pub secret: SecretString,
 }
 
+/// Application-provided hook used to recover from calls rejected because of missing scopes.
+///
+/// When USOS API rejects a call with [`Reason::ScopeMissing`](crate::api::errors::reason::Reason::ScopeMissing),
+/// the [`Client`] invokes this hook with the token used for the call and the scopes it lacks.
+/// The hook should run an incremental authorization (see [`acquire_request_token`] and [`acquire_access_token`])
+/// for the union of the scopes previously granted to the user and the missing ones, and return the new token.
+/// The call is then retried once with the returned token.
+///
+/// The client does not keep the new token. Either save it in the hook, or send the request with
+/// [`UsosRequestBuilder::request_with_recovered_token`](crate::client::UsosRequestBuilder::request_with_recovered_token),
+/// which returns it ([`UserSessions`](crate::sessions::UserSessions) does so and replaces the stored token).
+///
+/// Returning `None` gives up the recovery, in which case the call fails with [`AppError::ScopeMissing`].
+///
+/// The hook is implemented for any async closure with a matching signature.
+#[async_trait]
+pub trait ScopeRecovery: Send + Sync {
+    async fn recover(&self, token: AccessToken, missing: Scopes) -> Option<AccessToken>;
+}
+
+#[async_trait]
+impl<F, Fut> ScopeRecovery for F
+where
+    F: Fn(AccessToken, Scopes) -> Fut + Send + Sync,
+    Fut: Future<Output = Option<AccessToken>> + Send,
+{
+    async fn recover(&self, token: AccessToken, missing: Scopes) -> Option<AccessToken> {
+        self(token, missing).await
+    }
+}
+
 /// Acquires the request token, calling `services/oauth/request_token`.
 ///
 /// This function initiates the OAuth 1.0a authorization flow by requesting a temporary request token
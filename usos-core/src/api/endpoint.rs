@@ -0,0 +1,133 @@
+//! Typed description of USOS API methods.
+//!
+//! An [`Endpoint`] knows its path, authorization requirements, parameters and response type, so calling it with
+//! [`Client::call`](crate::client::Client::call) takes care of building the request and decoding the response.
+//!
+//! ```no_run
+//! use serde::Deserialize;
+//! use usos_core::{
+//!     api::{endpoint::{token, Endpoint, Requirement}, params::Params},
+//!     client::CLIENT,
+//! };
+//!
+//! #[derive(Deserialize)]
+//! struct Now(String);
+//!
+//! struct GetNow;
+//!
+//! impl Endpoint for GetNow {
+//!     const PATH: &'static str = "apisrv/now";
+//!     const CONSUMER: Requirement = Requirement::Ignored;
+//!     type Token = token::Ignored;
+//!     type Response = Now;
+//! }
+//!
+//! # async fn run() -> usos_core::Result<()> {
+//! let now = CLIENT.call(&GetNow).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Endpoints that require an access token can only be called with [`Client::call_with_token`](crate::client::Client::call_with_token),
+//! which is checked by the type system, as [`Client::call`](crate::client::Client::call) is only implemented for
+//! endpoints whose [`Endpoint::Token`] is [`NotRequired`]:
+//!
+//! ```compile_fail,E0277
+//! # use reqwest::Url;
+//! # use usos_core::{api::endpoint::{token, Endpoint, Requirement}, client::Client};
+//! struct GetUser;
+//!
+//! impl Endpoint for GetUser {
+//!     const PATH: &'static str = "users/user";
+//!     const CONSUMER: Requirement = Requirement::Required;
+//!     type Token = token::Required;
+//!     type Response = serde_json::Value;
+//! }
+//!
+//! let client = Client::new(Url::parse("https://apps.usos.pwr.edu.pl").unwrap());
+//! let user = client.call(&GetUser);
+//! ```
+
+use serde::de::DeserializeOwned;
+
+use super::params::Params;
+
+/// Whether a request has to be signed with a consumer key or an access token.
+///
+/// See the `auth_options` of methods in `apiref/method`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    /// The method cannot be called without it.
+    Required,
+    /// The method may behave differently with it.
+    Optional,
+    /// The method does not use it.
+    Ignored,
+}
+
+impl Requirement {
+    pub const fn is_required(self) -> bool {
+        matches!(self, Self::Required)
+    }
+}
+
+/// Type-level [`Requirement`]s of an access token, see [`Endpoint::Token`].
+pub mod token {
+    use super::{NotRequired, Requirement, TokenRequirement};
+
+    /// The method cannot be called without an access token.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Required;
+    /// The method may behave differently with an access token.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Optional;
+    /// The method does not use an access token.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Ignored;
+
+    impl TokenRequirement for Required {
+        const REQUIREMENT: Requirement = Requirement::Required;
+    }
+
+    impl TokenRequirement for Optional {
+        const REQUIREMENT: Requirement = Requirement::Optional;
+    }
+
+    impl TokenRequirement for Ignored {
+        const REQUIREMENT: Requirement = Requirement::Ignored;
+    }
+
+    impl NotRequired for Optional {}
+    impl NotRequired for Ignored {}
+}
+
+/// Implemented by the markers of the [`token`] module.
+pub trait TokenRequirement {
+    const REQUIREMENT: Requirement;
+}
+
+/// Token requirements that allow calling the endpoint without an access token.
+#[diagnostic::on_unimplemented(
+    message = "the endpoint requires an access token",
+    label = "the endpoint requires an access token",
+    note = "use `Client::call_with_token` instead"
+)]
+pub trait NotRequired: TokenRequirement {}
+
+/// A USOS API method.
+pub trait Endpoint {
+    /// Path of the method, relative to `services/`, e.g. `apisrv/installation`.
+    const PATH: &'static str;
+    /// Whether the request has to be signed with a consumer key.
+    const CONSUMER: Requirement;
+    /// Whether the request has to be signed with an access token, one of the markers of the [`token`] module.
+    type Token: TokenRequirement;
+
+    /// Type the response is decoded into.
+    type Response: DeserializeOwned;
+
+    /// Parameters of the request. Usually built with [`Params::from_serialize`] or the `From` conversions of [`Params`].
+    fn params(&self) -> crate::Result<Params> {
+        Ok(Params::from(()))
+    }
+}
@@ -1,14 +1,15 @@
 //! USOS API error handling utilities.
 
 use std::{
+    collections::HashMap,
     error::Error,
     fmt::{self, write, Display, Formatter},
 };
 
 use super::types::scopes::Scope;
 use reason::Reason;
-use serde::Deserialize;
-use serde_json::json;
+use serde::{de::Error as _, Deserialize, Deserializer};
+use serde_json::{json, Value};
 use user_message::UserMessages;
 pub mod reason;
 pub mod user_message;
@@ -28,6 +29,40 @@ pub struct UsosError {
     missing_scopes: Option<Vec<Scope>>,
 }
 
+impl UsosError {
+    /// Error description for the developer.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Error code, if USOS API provided one.
+    pub fn kind(&self) -> Option<&UsosErrorKind> {
+        self.kind.as_ref()
+    }
+
+    /// Error description designed to be shown to the user.
+    pub fn user_messages(&self) -> Option<&UserMessages> {
+        self.user_messages.as_ref()
+    }
+
+    /// Reason of the lack of permission, if the error was caused by a forbidden method, parameter or field.
+    pub fn reason(&self) -> Option<&Reason> {
+        match self.kind.as_ref()? {
+            UsosErrorKind::MethodForbidden { reason }
+            | UsosErrorKind::ParamForbidden { reason, .. }
+            | UsosErrorKind::FieldForbidden { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+
+    /// Scopes that the access token lacks.
+    ///
+    /// Present only if the error was caused by [`Reason::ScopeMissing`].
+    pub fn missing_scopes(&self) -> Option<&[Scope]> {
+        self.missing_scopes.as_deref()
+    }
+}
+
 impl Error for UsosError {}
 
 impl Display for UsosError {
@@ -55,9 +90,9 @@ impl Display for UsosError {
 }
 
 /// Possible error codes that USOS API can send.
-#[derive(Debug, Deserialize)]
-#[serde(tag = "error")]
-#[serde(rename_all = "snake_case")]
+///
+/// Error codes unknown to this crate, or known codes sent without the expected details, are represented by [`UsosErrorKind::Unknown`].
+#[derive(Debug)]
 pub enum UsosErrorKind {
     /// Access to the method is denied.
     MethodForbidden { reason: Reason },
@@ -98,13 +133,73 @@ pub enum UsosErrorKind {
     ObjectInvalid,
     /// Access to the referenced object was denied.
     ObjectForbidden,
+    /// Error code not known to this crate.
+    Unknown(String),
+}
+
+impl UsosErrorKind {
+    /// Builds the error kind from the fields of the error object. Returns `None` if a field required by the error code is missing.
+    fn from_fields(code: &str, fields: &HashMap<String, Value>) -> Option<Self> {
+        let field = |name: &str| fields.get(name)?.as_str().map(str::to_string);
+        let reason = || field("reason").map(Reason::from);
+
+        Some(match code {
+            "method_forbidden" => Self::MethodForbidden { reason: reason()? },
+            "param_missing" => Self::ParamMissing {
+                param_name: field("param_name")?,
+            },
+            "param_invalid" => Self::ParamInvalid {
+                param_name: field("param_name")?,
+            },
+            "param_forbidden" => Self::ParamForbidden {
+                param_name: field("param_name")?,
+                reason: reason()?,
+            },
+            "field_not_found" => Self::FieldNotFound {
+                field_name: field("field_name")?,
+                method_name: field("method_name")?,
+            },
+            "field_invalid" => Self::FieldInvalid {
+                field_name: field("field_name")?,
+                method_name: field("method_name")?,
+            },
+            "field_forbidden" => Self::FieldForbidden {
+                field_name: field("field_name")?,
+                method_name: field("method_name")?,
+                reason: reason()?,
+            },
+            "object_not_found" => Self::ObjectNotFound {
+                param_name: field("param_name")?,
+                method_name: field("method_name")?,
+            },
+            "object_invalid" => Self::ObjectInvalid,
+            "object_forbidden" => Self::ObjectForbidden,
+            _ => return None,
+        })
+    }
+}
+
+/// Deserializes the error object, with the error code under the `error` key.
+impl<'de> Deserialize<'de> for UsosErrorKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let fields = HashMap::<String, Value>::deserialize(deserializer)?;
+        let code = fields
+            .get("error")
+            .and_then(Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("error"))?;
+
+        Ok(Self::from_fields(code, &fields).unwrap_or_else(|| Self::Unknown(code.to_string())))
+    }
 }
 
 impl Display for UsosErrorKind {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
             UsosErrorKind::MethodForbidden { reason } => {
-                write!(f, "Method is forbidden - {reason}")
+                write!(f, "Method is forbidden - {}", reason.description())
             }
             UsosErrorKind::ParamMissing { param_name } => {
                 write!(f, "Parameter is missing: '{param_name}'")
@@ -115,7 +210,8 @@ impl Display for UsosErrorKind {
             UsosErrorKind::ParamForbidden { param_name, reason } => {
                 write!(
                     f,
-                    "Parameter is forbidden: '{param_name}' Reason: {reason})"
+                    "Parameter is forbidden: '{param_name}' Reason: {})",
+                    reason.description()
                 )
             }
             UsosErrorKind::FieldNotFound {
@@ -135,7 +231,8 @@ impl Display for UsosErrorKind {
                 reason,
             } => write!(
                 f,
-                "Field is forbidden: '{field_name}' Method: '{method_name}' Reason: {reason})"
+                "Field is forbidden: '{field_name}' Method: '{method_name}' Reason: {})",
+                reason.description()
             ),
             UsosErrorKind::ObjectNotFound {
                 param_name,
@@ -146,6 +243,7 @@ impl Display for UsosErrorKind {
             ),
             UsosErrorKind::ObjectInvalid => write!(f, "Object is invalid"),
             UsosErrorKind::ObjectForbidden => write!(f, "Object is forbidden"),
+            UsosErrorKind::Unknown(code) => write!(f, "Unknown error '{code}'"),
         }
     }
 }
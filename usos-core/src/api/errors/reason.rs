@@ -1,12 +1,14 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 /// Possible reasons for lack of permission to access resources from USOS API
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
+///
+/// Reason codes unknown to this crate are represented by [`Reason::Custom`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
 pub enum Reason {
     /// Consumer signature is missing;
     ConsumerMissing,
@@ -24,17 +26,59 @@ pub enum Reason {
     Custom(String),
 }
 
+impl Reason {
+    /// Human-readable description of the reason.
+    pub fn description(&self) -> &str {
+        match self {
+            Reason::ConsumerMissing => "consumer signature is missing",
+            Reason::UserMissing => "user's access token is required",
+            Reason::SecureRequired => "secure connection (SSL) is required",
+            Reason::TrustedRequired => "only administrative consumers are allowed",
+            Reason::ScopeMissing => "access token does not contain some of the required scopes",
+            Reason::ImpersonateRequired => "`as_user_id` parameter was used with a method which you do not have administrative access to",
+            Reason::Custom(s) => s,
+        }
+    }
+}
+
+/// Writes the reason code, as sent by USOS API.
 impl Display for Reason {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-			Reason::ConsumerMissing => write!(f, "consumer signature is missing"),
-			Reason::UserMissing => write!(f, "user's access token is required"),
-			Reason::SecureRequired => write!(f, "secure connection (SSL) is required"),
-			Reason::TrustedRequired => write!(f, "only administrative consumers are allowed"),
-			Reason::ScopeMissing => write!(f, "access token does not contain some of the required scopes"),
-			Reason::ImpersonateRequired => write!(f, "`as_user_id` parameter was used with a method which you do not have administrative access to"),
-			Reason::Custom(s) => write!(f, "{s}"),
-		}
+            Reason::ConsumerMissing => write!(f, "consumer_missing"),
+            Reason::UserMissing => write!(f, "user_missing"),
+            Reason::SecureRequired => write!(f, "secure_required"),
+            Reason::TrustedRequired => write!(f, "trusted_required"),
+            Reason::ScopeMissing => write!(f, "scope_missing"),
+            Reason::ImpersonateRequired => write!(f, "impersonate_required"),
+            Reason::Custom(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl From<&str> for Reason {
+    fn from(s: &str) -> Self {
+        match s {
+            "consumer_missing" => Self::ConsumerMissing,
+            "user_missing" => Self::UserMissing,
+            "secure_required" => Self::SecureRequired,
+            "trusted_required" => Self::TrustedRequired,
+            "scope_missing" => Self::ScopeMissing,
+            "impersonate_required" => Self::ImpersonateRequired,
+            _ => Self::Custom(s.to_string()),
+        }
+    }
+}
+
+impl From<String> for Reason {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<Reason> for String {
+    fn from(reason: Reason) -> Self {
+        reason.to_string()
     }
 }
 
@@ -42,14 +86,6 @@ impl FromStr for Reason {
     type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "consumer_missing" => Ok(Self::ConsumerMissing),
-            "user_missing" => Ok(Self::UserMissing),
-            "secure_required" => Ok(Self::SecureRequired),
-            "trusted_required" => Ok(Self::TrustedRequired),
-            "scope_missing" => Ok(Self::ScopeMissing),
-            "impersonate_required" => Ok(Self::ImpersonateRequired),
-            _ => Ok(Self::Custom(s.to_string())),
-        }
+        Ok(s.into())
     }
 }
@@ -1,7 +1,7 @@
-use crate::api::types::language::LanguageDictionary;
+use crate::api::types::language::{Language, LanguageDictionary};
 use serde::Deserialize;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::{self, Display, Formatter},
 };
 
@@ -14,20 +14,103 @@ pub struct UserMessages {
     fields: Option<HashMap<String, LanguageDictionary>>,
 }
 
+impl UserMessages {
+    /// Generic (context-free) message, in all languages.
+    pub fn generic_message(&self) -> Option<&LanguageDictionary> {
+        self.generic_message.as_ref()
+    }
+
+    /// Messages for parameters which have failed the validation, in all languages.
+    pub fn fields(&self) -> Option<&HashMap<String, LanguageDictionary>> {
+        self.fields.as_ref()
+    }
+
+    /// Messages for parameters which have failed the validation in the given language, keyed by parameter name.
+    ///
+    /// Useful for showing validation errors next to the matching inputs of a form.
+    pub fn field_messages(&self, language: &Language) -> BTreeMap<&str, &str> {
+        self.fields
+            .iter()
+            .flatten()
+            .map(|(field_name, message)| (field_name.as_str(), message.get(language)))
+            .collect()
+    }
+
+    /// Renders the generic message followed by the message of every invalid parameter (one per line, sorted by parameter name) in the given language.
+    pub fn render(&self, language: &Language) -> String {
+        let generic_message = self
+            .generic_message
+            .as_ref()
+            .map(|message| message.get(language).to_string());
+        let field_messages = self
+            .field_messages(language)
+            .into_iter()
+            .map(|(field_name, message)| format!("{field_name}: {message}"));
+
+        generic_message
+            .into_iter()
+            .chain(field_messages)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Renders the messages in English, use [`UserMessages::render`] for the language of the application user.
 impl Display for UserMessages {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if let Some(generic_message) = &self.generic_message {
-            write!(f, "{generic_message}")?;
-        }
-        if let Some(fields) = &self.fields {
-            write!(f, "Field errors:\n")?;
-            let message = fields
-                .iter()
-                .map(|(field_name, field_message)| format!("\t'{field_name}': {field_message}"))
-                .collect::<Vec<_>>()
-                .join("\n");
-            write!(f, "{message}")?;
-        }
-        Ok(())
+        write!(f, "{}", self.render(&Language::English))
     }
 }
+
+#[cfg(test)]
+fn form_messages() -> UserMessages {
+    UserMessages::deserialize(serde_json::json!({
+        "generic_message": {
+            "en": "Multiple errors in the form.",
+            "pl": "Formularz zawiera błędy."
+        },
+        "fields": {
+            "fac_id": {
+                "en": "This field is required.",
+                "pl": "To pole jest wymagane."
+            },
+            "course_id": {
+                "en": "Course no longer conducted. Select another.",
+                "pl": "Ten przedmiot nie jest już prowadzony. Wybierz inny."
+            }
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn messages_are_rendered_in_polish() {
+    assert_eq!(
+        form_messages().render(&Language::Polish),
+        "Formularz zawiera błędy.\n\
+         course_id: Ten przedmiot nie jest już prowadzony. Wybierz inny.\n\
+         fac_id: To pole jest wymagane."
+    );
+}
+
+#[test]
+fn field_messages_are_keyed_by_parameter() {
+    let messages = form_messages();
+    let fields = messages.field_messages(&Language::English);
+
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields["fac_id"], "This field is required.");
+}
+
+#[test]
+fn missing_parts_are_skipped() {
+    let messages = UserMessages::deserialize(serde_json::json!({
+        "fields": { "fac_id": { "en": "This field is required.", "pl": "To pole jest wymagane." } }
+    }))
+    .unwrap();
+
+    assert_eq!(
+        messages.render(&Language::English),
+        "fac_id: This field is required."
+    );
+}
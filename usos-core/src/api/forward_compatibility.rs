@@ -0,0 +1,146 @@
+//! Corpus of responses containing values unknown to this crate, as sent by newer USOS API versions.
+
+use rstest::rstest;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{fmt::Display, str::FromStr};
+
+use super::{
+    errors::{reason::Reason, UsosError, UsosErrorKind},
+    types::{
+        language::{Language, LanguageDictionary},
+        scopes::Scope,
+    },
+};
+
+/// Checks that the value survives `FromStr` -> `Display` and serde serialization -> deserialization unchanged.
+fn assert_round_trips<T>(code: &str, expected: T)
+where
+    T: FromStr + Display + Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+    T::Err: std::fmt::Debug,
+{
+    let parsed = code.parse::<T>().unwrap();
+    assert_eq!(parsed, expected);
+    assert_eq!(parsed.to_string(), code);
+
+    let serialized = serde_json::to_value(&parsed).unwrap();
+    assert_eq!(serialized, json!(code));
+    assert_eq!(serde_json::from_value::<T>(serialized).unwrap(), expected);
+}
+
+#[rstest]
+#[case("grades", Scope::Grades)]
+#[case("studies", Scope::Studies)]
+#[case(
+    "quantum_teleportation",
+    Scope::Unknown(String::from("quantum_teleportation"))
+)]
+fn scopes_round_trip(#[case] code: &str, #[case] expected: Scope) {
+    assert_round_trips(code, expected);
+}
+
+#[rstest]
+#[case("pl", Language::Polish)]
+#[case("en", Language::English)]
+#[case("de", Language::Unknown(String::from("de")))]
+fn languages_round_trip(#[case] code: &str, #[case] expected: Language) {
+    assert_round_trips(code, expected);
+}
+
+#[rstest]
+#[case("scope_missing", Reason::ScopeMissing)]
+#[case("user_missing", Reason::UserMissing)]
+#[case(
+    "not_in_office_hours",
+    Reason::Custom(String::from("not_in_office_hours"))
+)]
+fn reasons_round_trip(#[case] code: &str, #[case] expected: Reason) {
+    assert_round_trips(code, expected);
+}
+
+#[test]
+fn scope_list_with_unknown_scope_parses() {
+    let scopes = Vec::<Scope>::deserialize(json!(["grades", "quantum_teleportation"])).unwrap();
+
+    assert_eq!(
+        scopes,
+        [
+            Scope::Grades,
+            Scope::Unknown(String::from("quantum_teleportation"))
+        ]
+    );
+}
+
+#[test]
+fn dictionary_with_unknown_language_parses() {
+    let dictionary = LanguageDictionary::deserialize(json!({
+        "pl": "Informatyka",
+        "en": "Computer science",
+        "uk": "Інформатика",
+    }))
+    .unwrap();
+
+    assert_eq!(dictionary.english(), "Computer science");
+    assert_eq!(
+        dictionary.get(&Language::Unknown(String::from("uk"))),
+        "Інформатика"
+    );
+}
+
+#[rstest]
+#[case::unknown_code(json!({
+    "message": "The method is in maintenance mode.",
+    "error": "maintenance",
+}), "maintenance")]
+#[case::known_code_without_details(json!({
+    "message": "Required parameter is missing.",
+    "error": "param_missing",
+}), "param_missing")]
+fn unknown_error_kinds_parse(#[case] error: Value, #[case] code: &str) {
+    let error = UsosError::deserialize(&error).unwrap();
+
+    match error.kind() {
+        Some(UsosErrorKind::Unknown(unknown)) => assert_eq!(unknown, code),
+        other => panic!("Expected unknown error kind, got {other:?}"),
+    }
+}
+
+#[test]
+fn missing_scopes_with_unknown_scope_parse() {
+    let error = UsosError::deserialize(&json!({
+        "message": "Your access token lacks some of the required scopes.",
+        "error": "method_forbidden",
+        "reason": "scope_missing",
+        "missing_scopes": ["grades", "quantum_teleportation"],
+    }))
+    .unwrap();
+
+    assert!(matches!(
+        error.kind(),
+        Some(UsosErrorKind::MethodForbidden {
+            reason: Reason::ScopeMissing
+        })
+    ));
+    assert_eq!(
+        error.missing_scopes().unwrap(),
+        [
+            Scope::Grades,
+            Scope::Unknown(String::from("quantum_teleportation"))
+        ]
+    );
+}
+
+#[test]
+fn unknown_reason_parses() {
+    let error = UsosError::deserialize(&json!({
+        "message": "Access denied.",
+        "error": "method_forbidden",
+        "reason": "not_in_office_hours",
+    }))
+    .unwrap();
+
+    assert_eq!(
+        error.reason(),
+        Some(&Reason::Custom(String::from("not_in_office_hours")))
+    );
+}
@@ -1,6 +1,20 @@
-use super::{auth::AccessToken, oauth1::authorize};
-use crate::keys::ConsumerKey;
+use super::{
+    auth::AccessToken,
+    oauth1::authorize,
+    selector::Selector,
+    types::{
+        ids::{
+            BuildingId, ClassGroupId, CourseId, CourseUnitId, ExamId, FacultyId, ProgrammeId,
+            RoomId, TermId, UserId,
+        },
+        language::Language,
+        scopes::{Scope, Scopes},
+        time::{UsosDate, UsosDateTime, UsosTime},
+    },
+};
+use crate::{errors::AppError, keys::ConsumerKey};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::{
     collections::BTreeMap,
     ops::{Deref, DerefMut},
@@ -39,7 +53,7 @@ where
     U: Into<ParamString>,
 {
     fn from(value: (T, U)) -> Self {
-        Self(BTreeMap::from([(value.0.into(), value.1.into().0)]))
+        Self::from_iter([(value.0.into(), value.1.into())])
     }
 }
 
@@ -49,11 +63,7 @@ where
     U: Into<ParamString>,
 {
     fn from(value: [(T, U); N]) -> Self {
-        Self(BTreeMap::from_iter(
-            value
-                .into_iter()
-                .map(|pair| (pair.0.into(), pair.1.into().0)),
-        ))
+        Self::from_iter(value.map(|pair| (pair.0.into(), pair.1.into())))
     }
 }
 
@@ -77,11 +87,7 @@ where
     U: Into<ParamString>,
 {
     fn from(value: BTreeMap<T, U>) -> Self {
-        Self(BTreeMap::from_iter(
-            value
-                .into_iter()
-                .map(|pair| (pair.0.into(), pair.1.into().0)),
-        ))
+        Self::from_iter(value.into_iter().map(|pair| (pair.0.into(), pair.1.into())))
     }
 }
 
@@ -94,14 +100,83 @@ where
     }
 }
 
+/// Collects parameters, skipping the ones with omitted values.
+impl FromIterator<(String, ParamString)> for Params {
+    fn from_iter<I: IntoIterator<Item = (String, ParamString)>>(iter: I) -> Self {
+        Self(
+            iter.into_iter()
+                .filter_map(|(key, value)| Some((key, value.0?)))
+                .collect(),
+        )
+    }
+}
+
+impl Params {
+    /// Converts a serializable struct (or map) into parameters.
+    ///
+    /// Values are formatted the way USOS API expects them: `null` values are omitted, booleans become `true`/`false`,
+    /// lists are joined with `|` and nested objects are passed as JSON-encoded strings.
+    pub fn from_serialize(value: &impl Serialize) -> crate::Result<Self> {
+        let Value::Object(fields) =
+            serde_json::to_value(value).map_err(|e| AppError::InvalidRequest(e.to_string()))?
+        else {
+            return Err(AppError::InvalidRequest(String::from(
+                "Parameters must be serialized to an object",
+            )));
+        };
+
+        Ok(Self::from_iter(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, json_param(value))),
+        ))
+    }
+}
+
+fn json_param(value: Value) -> ParamString {
+    match value {
+        Value::Null => ParamString(None),
+        Value::String(s) => s.into(),
+        Value::Array(values) => ParamString(Some(
+            values
+                .into_iter()
+                .filter_map(|value| json_param(value).0)
+                .collect::<Vec<_>>()
+                .join("|"),
+        )),
+        other => other.to_string().into(),
+    }
+}
+
+/// Value of a single parameter, formatted the way USOS API expects it.
+///
+/// A `None` value means that the parameter is omitted from the request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ParamString(String);
+pub struct ParamString(Option<String>);
 
-impl Deref for ParamString {
-    type Target = String;
+impl<T: Into<ParamString>> From<Option<T>> for ParamString {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self(None), Into::into)
+    }
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+/// Joins the values with `|`.
+impl<T: Into<ParamString>> From<Vec<T>> for ParamString {
+    fn from(value: Vec<T>) -> Self {
+        Self(Some(
+            value
+                .into_iter()
+                .filter_map(|value| value.into().0)
+                .collect::<Vec<_>>()
+                .join("|"),
+        ))
+    }
+}
+
+/// Joins the values with `|`.
+impl<T: Into<ParamString> + Clone> From<&[T]> for ParamString {
+    fn from(value: &[T]) -> Self {
+        value.to_vec().into()
     }
 }
 
@@ -110,7 +185,7 @@ macro_rules! impl_into_param_string(
         $(
             impl From<$x> for ParamString {
                 fn from(value: $x) -> Self {
-					Self(value.to_string())
+					Self(Some(value.to_string()))
 				}
             }
         )*
@@ -118,5 +193,136 @@ macro_rules! impl_into_param_string(
 );
 
 impl_into_param_string!(
-    String, &str, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+    String,
+    &str,
+    &String,
+    bool,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    UsosDate,
+    UsosDateTime,
+    UsosTime,
+    Language,
+    Scope,
+    Scopes,
+    Selector,
+    UserId,
+    FacultyId,
+    CourseId,
+    CourseUnitId,
+    TermId,
+    ClassGroupId,
+    ProgrammeId,
+    RoomId,
+    BuildingId,
+    ExamId,
+    &UserId,
+    &FacultyId,
+    &CourseId,
+    &CourseUnitId,
+    &TermId,
+    &ClassGroupId,
+    &ProgrammeId,
+    &RoomId,
+    &BuildingId,
+    &ExamId
 );
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use time::macros::{date, datetime};
+
+    use super::*;
+
+    fn single(value: impl Into<ParamString>) -> Option<String> {
+        Params::from(("key", value)).0.remove("key")
+    }
+
+    #[test]
+    fn values_are_formatted() {
+        assert_eq!(single(true), Some(String::from("true")));
+        assert_eq!(single(42u32), Some(String::from("42")));
+        assert_eq!(
+            single(UsosDate(date!(2024 - 10 - 01))),
+            Some(String::from("2024-10-01"))
+        );
+        assert_eq!(
+            single(UsosDateTime(datetime!(2024-10-01 08:15))),
+            Some(String::from("2024-10-01 08:15:00"))
+        );
+        assert_eq!(single(Language::Polish), Some(String::from("pl")));
+        let term = &TermId::new("2024/25-Z");
+        assert_eq!(single(term), Some(String::from("2024/25-Z")));
+        assert_eq!(
+            single("id|name".parse::<Selector>().unwrap()),
+            Some(String::from("id|name"))
+        );
+    }
+
+    #[test]
+    fn missing_optional_values_are_omitted() {
+        let params = Params::from([("a", Some("1")), ("b", None)]);
+
+        assert_eq!(params.0, BTreeMap::from([("a".into(), "1".into())]));
+    }
+
+    #[test]
+    fn lists_are_joined_with_pipes() {
+        assert_eq!(single(vec![1, 2, 3]), Some(String::from("1|2|3")));
+        assert_eq!(single(&["a", "b"][..]), Some(String::from("a|b")));
+        assert_eq!(single(Vec::<u32>::new()), Some(String::new()));
+    }
+
+    #[derive(Serialize)]
+    struct SearchRequest {
+        query: &'static str,
+        num: u32,
+        visible_only: bool,
+        start: Option<u32>,
+        fac_ids: Vec<&'static str>,
+        since: UsosDate,
+    }
+
+    #[test]
+    fn serializable_structs_are_converted() {
+        let params = Params::from_serialize(&SearchRequest {
+            query: "informatyka",
+            num: 20,
+            visible_only: true,
+            start: None,
+            fac_ids: vec!["W4", "W8"],
+            since: UsosDate(date!(2024 - 10 - 01)),
+        })
+        .unwrap();
+
+        assert_eq!(
+            params.0,
+            BTreeMap::from_iter(
+                [
+                    ("query", "informatyka"),
+                    ("num", "20"),
+                    ("visible_only", "true"),
+                    ("fac_ids", "W4|W8"),
+                    ("since", "2024-10-01"),
+                ]
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn non_objects_are_rejected() {
+        assert!(Params::from_serialize(&[1, 2]).is_err());
+    }
+}
@@ -0,0 +1,445 @@
+//! Field selectors, used in the `fields` parameter of many USOS API methods.
+//!
+//! A selector lists the fields of the returned object, separated with `|`. Subfields of a field are listed in square brackets, e.g. `id|name|faculty[id|name]`.
+//! See the "fields" section of [the USOS API reference](https://apps.usos.pw.edu.pl/developers/api/definitions/selectors/).
+//!
+//! ```
+//! use usos_core::{api::selector::Selector, selector};
+//!
+//! let built = Selector::new()
+//!     .field("id")
+//!     .nested("faculty", Selector::new().field("id").field("name"));
+//! let parsed: Selector = "id|faculty[id|name]".parse().unwrap();
+//!
+//! assert_eq!(built, parsed);
+//! assert_eq!(selector!(id | faculty[id | name]), parsed);
+//! assert_eq!(parsed.to_string(), "id|faculty[id|name]");
+//! ```
+
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+use crate::errors::AppError;
+
+/// Derives [`UsosFields`] for a struct, so that it selects exactly the fields it deserializes.
+///
+/// Fields are selected under their serde names. Fields of nested structs marked with `#[usos(nested)]` are selected with their subfields.
+///
+/// ```
+/// use serde::Deserialize;
+/// use usos_core::api::selector::UsosFields;
+///
+/// #[derive(Deserialize, UsosFields)]
+/// struct Faculty {
+///     id: String,
+///     #[serde(rename = "name")]
+///     faculty_name: Option<String>,
+/// }
+///
+/// #[derive(Deserialize, UsosFields)]
+/// struct User {
+///     id: String,
+///     #[usos(nested)]
+///     faculties: Vec<Faculty>,
+/// }
+///
+/// assert_eq!(User::selector().to_string(), "id|faculties[id|name]");
+/// ```
+pub use usos_macros::UsosFields;
+
+/// Types that know the [`Selector`] of the USOS API fields they are deserialized from.
+///
+/// Usually derived, see [the derive macro](macro@UsosFields).
+pub trait UsosFields {
+    /// The selector of all fields of the type.
+    fn selector() -> Selector;
+}
+
+/// A single field of a [`Selector`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Field {
+    /// Field without subfields, e.g. `id`.
+    One(String),
+    /// Field with selected subfields, e.g. `faculty[id|name]`.
+    Nested(String, Vec<Field>),
+}
+
+impl Field {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::One(name) | Self::Nested(name, _) => name,
+        }
+    }
+
+    /// Selected subfields, empty for [`Field::One`].
+    pub fn subfields(&self) -> &[Field] {
+        match self {
+            Self::One(_) => &[],
+            Self::Nested(_, subfields) => subfields,
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::One(name) => write!(f, "{name}"),
+            Self::Nested(name, subfields) => write!(f, "{name}[{}]", Fields(subfields)),
+        }
+    }
+}
+
+/// Formats fields separated with `|`.
+struct Fields<'a>(&'a [Field]);
+
+impl Display for Fields<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, field) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            write!(f, "{field}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors that can occur while parsing or validating a [`Selector`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    /// The selector string is malformed.
+    #[error("Invalid selector syntax at position {position}: {message}")]
+    Syntax { position: usize, message: String },
+    /// The selected field is not returned by the method.
+    #[error("Field '{0}' is not returned by the method")]
+    UnknownField(String),
+}
+
+impl From<SelectorError> for AppError {
+    fn from(value: SelectorError) -> Self {
+        Self::InvalidRequest(value.to_string())
+    }
+}
+
+/// Fields selected from the object returned by a USOS API method.
+///
+/// Build it with [`Selector::field`] and [`Selector::nested`], the [`selector!`](crate::selector!) macro, or parse it from a string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selector(Vec<Field>);
+
+impl Selector {
+    /// Creates an empty selector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field without subfields.
+    pub fn field(self, name: impl Into<String>) -> Self {
+        self.with_field(Field::One(name.into()))
+    }
+
+    /// Adds a field with the given subfields.
+    pub fn nested(self, name: impl Into<String>, subfields: Selector) -> Self {
+        self.with_field(Field::Nested(name.into(), subfields.0))
+    }
+
+    /// Adds a field.
+    pub fn with_field(mut self, field: Field) -> Self {
+        self.0.push(field);
+        self
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.0
+    }
+
+    pub fn into_fields(self) -> Vec<Field> {
+        self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Merges two selectors into one selecting the fields of both.
+    ///
+    /// Fields present in both selectors appear once, with their subfields merged. A field selected without subfields
+    /// in either selector stays bare (e.g. `faculty` and `faculty[id]` give `faculty`), so that the merged selector
+    /// never selects less than one of the merged ones.
+    pub fn merge(mut self, other: Selector) -> Self {
+        for field in other.0 {
+            merge_field(&mut self.0, field);
+        }
+        self
+    }
+
+    /// Checks that every top-level field is one of `known_fields`, e.g. the `result_fields` of the method from `apiref/method`.
+    ///
+    /// Subfields are not checked, as USOS API does not describe them.
+    pub fn validate<'a>(
+        &self,
+        known_fields: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), SelectorError> {
+        let known_fields = known_fields.into_iter().collect::<Vec<_>>();
+        match self
+            .0
+            .iter()
+            .find(|field| !known_fields.contains(&field.name()))
+        {
+            Some(field) => Err(SelectorError::UnknownField(field.name().to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+fn merge_field(fields: &mut Vec<Field>, field: Field) {
+    let Some(existing) = fields.iter_mut().find(|f| f.name() == field.name()) else {
+        fields.push(field);
+        return;
+    };
+
+    match (
+        std::mem::replace(existing, Field::One(String::new())),
+        field,
+    ) {
+        (Field::One(name), _) | (Field::Nested(name, _), Field::One(_)) => {
+            *existing = Field::One(name)
+        }
+        (Field::Nested(name, mut subfields), Field::Nested(_, other)) => {
+            for subfield in other {
+                merge_field(&mut subfields, subfield);
+            }
+            *existing = Field::Nested(name, subfields);
+        }
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Fields(&self.0))
+    }
+}
+
+impl From<Selector> for String {
+    fn from(selector: Selector) -> Self {
+        selector.to_string()
+    }
+}
+
+impl FromIterator<Field> for Selector {
+    fn from_iter<T: IntoIterator<Item = Field>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            input: s,
+            position: 0,
+        };
+        if s.is_empty() {
+            return Ok(Self::new());
+        }
+
+        let fields = parser.fields()?;
+        match parser.peek() {
+            None => Ok(Self(fields)),
+            Some(c) => Err(parser.error(format!("unexpected '{c}'"))),
+        }
+    }
+}
+
+/// Recursive descent parser of the selector syntax.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn error(&self, message: impl Into<String>) -> SelectorError {
+        SelectorError::Syntax {
+            position: self.position,
+            message: message.into(),
+        }
+    }
+
+    /// fields := field ('|' field)*
+    fn fields(&mut self) -> Result<Vec<Field>, SelectorError> {
+        let mut fields = vec![self.field()?];
+        while self.peek() == Some('|') {
+            self.position += 1;
+            fields.push(self.field()?);
+        }
+        Ok(fields)
+    }
+
+    /// field := name ('[' fields ']')?
+    fn field(&mut self) -> Result<Field, SelectorError> {
+        let rest = &self.input[self.position..];
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("expected a field name"));
+        }
+        let name = rest[..length].to_string();
+        self.position += length;
+
+        if self.peek() != Some('[') {
+            return Ok(Field::One(name));
+        }
+        self.position += 1;
+        let subfields = self.fields()?;
+        if self.peek() != Some(']') {
+            return Err(self.error("expected ']'"));
+        }
+        self.position += 1;
+        Ok(Field::Nested(name, subfields))
+    }
+}
+
+/// Builds a [`Selector`](crate::api::selector::Selector) using the USOS API selector syntax.
+///
+/// ```
+/// use usos_core::selector;
+///
+/// let selector = selector!(id | name | faculty[id | name[pl]]);
+/// assert_eq!(selector.to_string(), "id|name|faculty[id|name[pl]]");
+/// ```
+#[macro_export]
+macro_rules! selector {
+    (@field $name:ident) => {
+        $crate::api::selector::Field::One(stringify!($name).to_string())
+    };
+    (@field $name:ident [$($subfields:tt)*]) => {
+        $crate::api::selector::Field::Nested(
+            stringify!($name).to_string(),
+            $crate::selector!($($subfields)*).into_fields(),
+        )
+    };
+    () => {
+        $crate::api::selector::Selector::new()
+    };
+    ($($name:ident $([$($subfields:tt)*])?)|+) => {
+        $crate::api::selector::Selector::new()
+            $(.with_field($crate::selector!(@field $name $([$($subfields)*])?)))+
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(Selector::new(), "")]
+    #[case(Selector::new().field("a"), "a")]
+    #[case(Selector::new().field("a").field("b"), "a|b")]
+    #[case(Selector::new().nested("a", Selector::new().field("b").field("c")), "a[b|c]")]
+    #[case(Selector::new().nested("a", Selector::new().field("b")).field("c"), "a[b]|c")]
+    #[case(
+        Selector::new().field("a").nested("b", Selector::new().field("c").field("d")).field("e"),
+        "a|b[c|d]|e"
+    )]
+    fn format_selectors_is_correct(#[case] selector: Selector, #[case] expected: &str) {
+        assert_eq!(selector.to_string(), expected);
+        assert_eq!(expected.parse::<Selector>().unwrap(), selector);
+    }
+
+    #[rstest]
+    #[case("|", 0)]
+    #[case("a|", 2)]
+    #[case("a[b", 3)]
+    #[case("a[]", 2)]
+    #[case("a]b", 1)]
+    #[case("a b", 1)]
+    fn invalid_selectors_are_rejected(#[case] input: &str, #[case] expected_position: usize) {
+        match input.parse::<Selector>() {
+            Err(SelectorError::Syntax { position, .. }) => assert_eq!(position, expected_position),
+            other => panic!("Expected syntax error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn macro_builds_nested_selectors() {
+        assert_eq!(selector!(), Selector::new());
+        assert_eq!(
+            selector!(id | name[pl | en] | type),
+            "id|name[pl|en]|type".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn merged_selectors_contain_fields_of_both() {
+        let a: Selector = "id|name[pl]|faculty".parse().unwrap();
+        let b: Selector = "name[en]|faculty[id]|email".parse().unwrap();
+
+        assert_eq!(a.merge(b).to_string(), "id|name[pl|en]|faculty|email");
+    }
+
+    #[test]
+    fn bare_fields_are_kept_when_merged() {
+        let bare: Selector = "faculty".parse().unwrap();
+        let nested: Selector = "faculty[id]".parse().unwrap();
+
+        assert_eq!(bare.clone().merge(nested.clone()).to_string(), "faculty");
+        assert_eq!(nested.merge(bare).to_string(), "faculty");
+    }
+
+    #[test]
+    fn unknown_fields_are_reported() {
+        let selector: Selector = "id|name[pl]|surname".parse().unwrap();
+
+        assert_eq!(selector.validate(["id", "name", "surname"]), Ok(()));
+        assert_eq!(
+            selector.validate(["id", "name"]),
+            Err(SelectorError::UnknownField(String::from("surname")))
+        );
+    }
+
+    #[derive(serde::Deserialize, UsosFields)]
+    struct Name {
+        pl: Option<String>,
+        en: Option<String>,
+    }
+
+    #[derive(serde::Deserialize, UsosFields)]
+    struct Common {
+        id: String,
+    }
+
+    #[derive(serde::Deserialize, UsosFields)]
+    struct Programme {
+        #[serde(flatten)]
+        common: Common,
+        #[serde(rename = "description")]
+        programme_description: Option<String>,
+        #[usos(nested)]
+        name: Option<Name>,
+        #[usos(nested)]
+        translations: Vec<Name>,
+        #[serde(skip)]
+        cached: bool,
+        r#type: String,
+    }
+
+    #[test]
+    fn derived_selector_follows_serde_names() {
+        assert_eq!(
+            Programme::selector().to_string(),
+            "id|description|name[pl|en]|translations[pl|en]|type"
+        );
+    }
+}
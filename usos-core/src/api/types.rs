@@ -1,5 +1,6 @@
 //! Custom utility types for handling specific data types that USOS API provides.
 
+pub mod ids;
 pub mod language;
 pub mod scopes;
 pub mod time;
@@ -0,0 +1,151 @@
+//! Identifiers of USOS entities.
+//!
+//! Every kind of entity has its own type, so an identifier of one kind cannot be passed where another is expected.
+//! USOS API sends some identifiers as strings and some as numbers. All of them are stored as strings and accept both forms when deserializing.
+
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Identifier sent either as a string or as a number.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawId {
+    String(String),
+    Number(u64),
+}
+
+impl From<RawId> for String {
+    fn from(id: RawId) -> Self {
+        match id {
+            RawId::String(id) => id,
+            RawId::Number(id) => id.to_string(),
+        }
+    }
+}
+
+macro_rules! usos_id {
+    ($($(#[$meta:meta])* $name:ident),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+            #[serde(transparent)]
+            pub struct $name(String);
+
+            impl $name {
+                pub fn new(id: impl Into<String>) -> Self {
+                    Self(id.into())
+                }
+
+                pub fn as_str(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl Display for $name {
+                fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                    write!(f, "{}", self.0)
+                }
+            }
+
+            impl FromStr for $name {
+                type Err = Infallible;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    Ok(Self::new(s))
+                }
+            }
+
+            impl From<String> for $name {
+                fn from(id: String) -> Self {
+                    Self(id)
+                }
+            }
+
+            impl From<&str> for $name {
+                fn from(id: &str) -> Self {
+                    Self::new(id)
+                }
+            }
+
+            impl From<$name> for String {
+                fn from(id: $name) -> Self {
+                    id.0
+                }
+            }
+
+            impl AsRef<str> for $name {
+                fn as_ref(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    RawId::deserialize(deserializer).map(|id| Self(id.into()))
+                }
+            }
+        )*
+    };
+}
+
+usos_id!(
+    /// Identifier of a user (`user_id`).
+    UserId,
+    /// Identifier of a faculty or another organizational unit (`fac_id`).
+    FacultyId,
+    /// Identifier of a course (`course_id`), e.g. `"INZ002007C"`.
+    CourseId,
+    /// Identifier of a course unit (`course_unit_id`), i.e. a course conducted in a given term.
+    CourseUnitId,
+    /// Identifier of an academic term (`term_id`), e.g. `"2024/25-Z"`.
+    TermId,
+    /// Identifier of a class group (`class_group_id`).
+    ClassGroupId,
+    /// Identifier of a study programme (`programme_id`).
+    ProgrammeId,
+    /// Identifier of a room (`room_id`).
+    RoomId,
+    /// Identifier of a building (`building_id`).
+    BuildingId,
+    /// Identifier of an exam (`exam_id`).
+    ExamId,
+);
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn ids_are_deserialized_from_strings_and_numbers() {
+        assert_eq!(
+            FacultyId::deserialize(json!("W4N")).unwrap(),
+            FacultyId::new("W4N")
+        );
+        assert_eq!(
+            RoomId::deserialize(json!(1234)).unwrap(),
+            RoomId::new("1234")
+        );
+    }
+
+    #[test]
+    fn ids_are_serialized_as_strings() {
+        assert_eq!(
+            serde_json::to_value(TermId::new("2024/25-Z")).unwrap(),
+            json!("2024/25-Z")
+        );
+        assert_eq!(
+            "2024/25-Z".parse::<TermId>().unwrap().to_string(),
+            "2024/25-Z"
+        );
+    }
+}
@@ -1,44 +1,104 @@
 //! The language dictionary
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
+    convert::Infallible,
     fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
 /// A type designed to contain human-readable responses in multiple languages.
 ///
+/// USOS API often omits translations or sends `null` instead of them, so the dictionary may contain any subset of languages.
+/// Entries with `null` values are skipped when deserializing.
+///
 /// See the "Language" section of [the USOS API reference](https://apps.usos.pw.edu.pl/developers/api/definitions/datatypes/).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "HashMap<Language, Option<String>>")]
 pub struct LanguageDictionary(HashMap<Language, String>);
 
+impl From<HashMap<Language, Option<String>>> for LanguageDictionary {
+    fn from(entries: HashMap<Language, Option<String>>) -> Self {
+        Self(
+            entries
+                .into_iter()
+                .filter_map(|(language, text)| Some((language, text?)))
+                .collect(),
+        )
+    }
+}
+
+impl FromIterator<(Language, String)> for LanguageDictionary {
+    fn from_iter<T: IntoIterator<Item = (Language, String)>>(iter: T) -> Self {
+        Self(HashMap::from_iter(iter))
+    }
+}
+
+/// Writes the text in English, falling back to other languages if it is missing.
+///
+/// To write it in the language of the application user, use [`LanguageDictionary::get`] with
+/// [`Client::language`](crate::client::Client::language).
 impl Display for LanguageDictionary {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.english())
+        write!(f, "{}", self.get(&Language::English))
     }
 }
 
 impl LanguageDictionary {
-    pub fn get(&self, language: Language) -> &str {
-        self.0.get(&language).unwrap()
+    /// Languages tried, in order, when the requested one is missing.
+    pub const DEFAULT_FALLBACK: &'static [Language] = &[Language::English, Language::Polish];
+
+    /// Returns the text in the given language, if present.
+    pub fn try_get(&self, language: &Language) -> Option<&str> {
+        self.0.get(language).map(String::as_str)
+    }
+
+    /// Returns the text in the first language of `chain` that is present.
+    pub fn get_with_fallback(&self, chain: &[Language]) -> Option<&str> {
+        chain.iter().find_map(|language| self.try_get(language))
+    }
+
+    /// Returns the text in the given language.
+    ///
+    /// If it is missing, the languages of [`DEFAULT_FALLBACK`](Self::DEFAULT_FALLBACK) are tried, then the language
+    /// with the alphabetically first code, so that the same text is chosen every time.
+    /// Returns an empty string if the dictionary is empty.
+    pub fn get(&self, language: &Language) -> &str {
+        self.try_get(language)
+            .or_else(|| self.get_with_fallback(Self::DEFAULT_FALLBACK))
+            .or_else(|| {
+                self.0
+                    .iter()
+                    .min_by_key(|(language, _)| language.to_string())
+                    .map(|(_, text)| text.as_str())
+            })
+            .unwrap_or_default()
     }
 
     pub fn polish(&self) -> &str {
-        self.get(Language::Polish)
+        self.get(&Language::Polish)
     }
 
     pub fn english(&self) -> &str {
-        self.get(Language::English)
+        self.get(&Language::English)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 }
 
 /// All languages supported in user-friendly responses.
-#[derive(Debug, Deserialize, Hash, Eq, PartialEq)]
+///
+/// Languages unknown to this crate are represented by [`Language::Unknown`].
+#[derive(Debug, Serialize, Deserialize, Hash, Eq, PartialEq, Clone)]
+#[serde(from = "String", into = "String")]
 pub enum Language {
-    #[serde(rename = "pl")]
     Polish,
-    #[serde(rename = "en")]
     English,
+    /// Language not known to this crate, with its code.
+    Unknown(String),
 }
 
 impl Display for Language {
@@ -46,6 +106,110 @@ impl Display for Language {
         match self {
             Language::Polish => write!(f, "pl"),
             Language::English => write!(f, "en"),
+            Language::Unknown(code) => write!(f, "{code}"),
+        }
+    }
+}
+
+impl From<&str> for Language {
+    fn from(s: &str) -> Self {
+        match s {
+            "pl" => Self::Polish,
+            "en" => Self::English,
+            other => Self::Unknown(other.to_string()),
         }
     }
 }
+
+impl From<String> for Language {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.to_string()
+    }
+}
+
+impl FromStr for Language {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn null_translations_are_skipped() {
+        // services/fac/faculty of a faculty without an English name
+        let name = LanguageDictionary::deserialize(json!({
+            "pl": "Wydział Informatyki i Telekomunikacji",
+            "en": null
+        }))
+        .unwrap();
+
+        assert_eq!(name.try_get(&Language::English), None);
+        assert_eq!(name.english(), "Wydział Informatyki i Telekomunikacji");
+    }
+
+    #[test]
+    fn fallback_chain_is_followed() {
+        let name = LanguageDictionary::deserialize(json!({
+            "en": "Computer Science",
+            "uk": "Інформатика"
+        }))
+        .unwrap();
+        let ukrainian = Language::Unknown(String::from("uk"));
+
+        assert_eq!(name.polish(), "Computer Science");
+        assert_eq!(
+            name.get_with_fallback(&[Language::Polish, ukrainian.clone()]),
+            Some("Інформатика")
+        );
+        assert_eq!(name.get_with_fallback(&[Language::Polish]), None);
+    }
+
+    #[test]
+    fn unknown_languages_are_chosen_by_code() {
+        let name = LanguageDictionary::deserialize(json!({
+            "uk": "Інформатика",
+            "de": "Informatik",
+            "fr": "Informatique"
+        }))
+        .unwrap();
+
+        assert_eq!(name.polish(), "Informatik");
+        assert_eq!(name.to_string(), "Informatik");
+    }
+
+    #[test]
+    fn empty_dictionary_gives_empty_text() {
+        let name = LanguageDictionary::deserialize(json!({ "pl": null, "en": null })).unwrap();
+
+        assert!(name.is_empty());
+        assert_eq!(name.to_string(), "");
+    }
+
+    #[test]
+    fn dictionary_round_trips_through_serde() {
+        let name = LanguageDictionary::from_iter([
+            (Language::Polish, String::from("Matematyka")),
+            (Language::English, String::from("Mathematics")),
+        ]);
+
+        let serialized = serde_json::to_value(&name).unwrap();
+        assert_eq!(
+            serialized,
+            json!({ "pl": "Matematyka", "en": "Mathematics" })
+        );
+        assert_eq!(LanguageDictionary::deserialize(serialized).unwrap(), name);
+    }
+}
@@ -1,13 +1,18 @@
 //! The USOS API time types.
 //!
 //! Note: USOS API provides time based on the Polish time zone, which is UTC+1 normally and UTC+2 in a daylight savings time
-//! starting from the last Sunday in March and ending in the last Sunday in October. The types in this module **do not** include any time zone offsets.
+//! starting from the last Sunday in March and ending in the last Sunday in October. The types in this module **do not** include any time zone offsets,
+//! but they can be converted to and from [`OffsetDateTime`] using the Polish DST rules, e.g. [`UsosDateTime::to_offset_datetime`].
+//!
+//! The clocks are moved forward from 02:00 to 03:00 on the last Sunday in March and back from 03:00 to 02:00 on the last Sunday in October
+//! (both at 01:00 UTC), so local times between 02:00 and 03:00 are either non-existent or ambiguous on these days. See [`WarsawDateTime`].
 
 use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 use time::format_description::BorrowedFormatItem;
-use time::macros::format_description;
+use time::macros::{format_description, offset, time};
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
 /// Date format yyyy-mm-dd
 pub const DATE_FORMAT: &[BorrowedFormatItem<'_>] = format_description!("[year]-[month]-[day]");
@@ -110,11 +115,177 @@ impl Display for UsosPreciseDateTime {
     }
 }
 
+/// Polish standard time offset (CET, UTC+1).
+pub const STANDARD_OFFSET: UtcOffset = offset!(+1);
+/// Polish daylight saving time offset (CEST, UTC+2).
+pub const DST_OFFSET: UtcOffset = offset!(+2);
+
+/// Local Polish time resolved to an instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarsawDateTime {
+    /// The local time occurs exactly once.
+    Unique(OffsetDateTime),
+    /// The local time occurs twice, because the clocks are moved back in late October.
+    /// `earlier` is in daylight saving time, `later` in standard time.
+    Ambiguous {
+        earlier: OffsetDateTime,
+        later: OffsetDateTime,
+    },
+    /// The local time does not exist, because the clocks are moved forward in late March.
+    /// `shifted` is the instant obtained by moving the local time forward by the length of the gap (e.g. 02:30 becomes 03:30).
+    NonExistent { shifted: OffsetDateTime },
+}
+
+impl WarsawDateTime {
+    /// Resolves the local Polish time.
+    pub fn resolve(local: PrimitiveDateTime) -> Self {
+        let standard = local.assume_offset(STANDARD_OFFSET);
+        let dst = local.assume_offset(DST_OFFSET);
+        let standard_valid = warsaw_offset(standard) == STANDARD_OFFSET;
+        let dst_valid = warsaw_offset(dst) == DST_OFFSET;
+
+        match (dst_valid, standard_valid) {
+            (true, true) => Self::Ambiguous {
+                earlier: dst,
+                later: standard,
+            },
+            (true, false) => Self::Unique(dst),
+            (false, true) => Self::Unique(standard),
+            (false, false) => Self::NonExistent {
+                shifted: standard.to_offset(DST_OFFSET),
+            },
+        }
+    }
+
+    /// Returns the instant if the local time is unique.
+    pub fn unique(self) -> Option<OffsetDateTime> {
+        match self {
+            Self::Unique(datetime) => Some(datetime),
+            _ => None,
+        }
+    }
+
+    /// Returns the earlier instant of an ambiguous time, or the shifted instant of a non-existent time.
+    pub fn earliest(self) -> OffsetDateTime {
+        match self {
+            Self::Unique(datetime)
+            | Self::Ambiguous {
+                earlier: datetime, ..
+            }
+            | Self::NonExistent { shifted: datetime } => datetime,
+        }
+    }
+
+    /// Returns the later instant of an ambiguous time, or the shifted instant of a non-existent time.
+    pub fn latest(self) -> OffsetDateTime {
+        match self {
+            Self::Unique(datetime)
+            | Self::Ambiguous {
+                later: datetime, ..
+            }
+            | Self::NonExistent { shifted: datetime } => datetime,
+        }
+    }
+}
+
+/// Returns the Polish time zone offset in effect at the given instant.
+pub fn warsaw_offset(datetime: OffsetDateTime) -> UtcOffset {
+    let utc = datetime.to_offset(UtcOffset::UTC);
+    let utc = PrimitiveDateTime::new(utc.date(), utc.time());
+    let transition = |month| PrimitiveDateTime::new(last_sunday(utc.year(), month), time!(01:00));
+
+    if (transition(Month::March)..transition(Month::October)).contains(&utc) {
+        DST_OFFSET
+    } else {
+        STANDARD_OFFSET
+    }
+}
+
+/// Converts the instant to the local Polish time.
+pub fn to_warsaw_time(datetime: OffsetDateTime) -> PrimitiveDateTime {
+    let local = datetime.to_offset(warsaw_offset(datetime));
+    PrimitiveDateTime::new(local.date(), local.time())
+}
+
+fn last_sunday(year: i32, month: Month) -> Date {
+    let last_day = Date::from_calendar_date(year, month, month.length(year))
+        .expect("the last day of a month is a valid date");
+    let days_after_sunday = last_day.weekday().number_days_from_sunday();
+    last_day - Duration::days(days_after_sunday.into())
+}
+
+impl UsosDate {
+    /// Returns the instant at which the day starts in Poland.
+    pub fn to_offset_datetime(&self) -> OffsetDateTime {
+        // transitions happen at night, so the midnight is always unique
+        WarsawDateTime::resolve(self.0.midnight()).earliest()
+    }
+
+    /// Returns the Polish date at the given instant.
+    pub fn from_offset_datetime(datetime: OffsetDateTime) -> Self {
+        Self(to_warsaw_time(datetime).date())
+    }
+}
+
+impl UsosDateTime {
+    /// Resolves the local Polish time, reporting ambiguous and non-existent times explicitly.
+    pub fn resolve(&self) -> WarsawDateTime {
+        WarsawDateTime::resolve(self.0)
+    }
+
+    /// Converts the local Polish time to an instant in the Polish time zone.
+    ///
+    /// The earlier instant is chosen for ambiguous times, and non-existent times are shifted forward (see [`WarsawDateTime::earliest`]).
+    /// Use [`UsosDateTime::resolve`] to handle these cases differently.
+    pub fn to_offset_datetime(&self) -> OffsetDateTime {
+        self.resolve().earliest()
+    }
+
+    /// Converts the local Polish time to an instant in UTC. See [`UsosDateTime::to_offset_datetime`].
+    pub fn to_utc(&self) -> OffsetDateTime {
+        self.to_offset_datetime().to_offset(UtcOffset::UTC)
+    }
+
+    /// Returns the local Polish time at the given instant.
+    pub fn from_offset_datetime(datetime: OffsetDateTime) -> Self {
+        Self(to_warsaw_time(datetime))
+    }
+}
+
+impl UsosPreciseDateTime {
+    /// Resolves the local Polish time, reporting ambiguous and non-existent times explicitly.
+    pub fn resolve(&self) -> WarsawDateTime {
+        WarsawDateTime::resolve(self.0)
+    }
+
+    /// Converts the local Polish time to an instant in the Polish time zone. See [`UsosDateTime::to_offset_datetime`].
+    pub fn to_offset_datetime(&self) -> OffsetDateTime {
+        self.resolve().earliest()
+    }
+
+    /// Converts the local Polish time to an instant in UTC. See [`UsosDateTime::to_offset_datetime`].
+    pub fn to_utc(&self) -> OffsetDateTime {
+        self.to_offset_datetime().to_offset(UtcOffset::UTC)
+    }
+
+    /// Returns the local Polish time at the given instant.
+    pub fn from_offset_datetime(datetime: OffsetDateTime) -> Self {
+        Self(to_warsaw_time(datetime))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::api::types::time::{UsosDate, UsosDateTime, UsosPreciseDateTime, UsosTime};
+    use crate::api::types::time::{
+        UsosDate, UsosDateTime, UsosPreciseDateTime, UsosTime, WarsawDateTime, DST_OFFSET,
+        STANDARD_OFFSET,
+    };
+    use rstest::rstest;
     use serde::Deserialize;
-    use time::{Date, PrimitiveDateTime, Time};
+    use time::{
+        macros::{date, datetime},
+        Date, PrimitiveDateTime, Time,
+    };
 
     #[test]
     fn valid_date_string() {
@@ -173,4 +344,92 @@ mod tests {
             )
         );
     }
+
+    #[rstest]
+    #[case::winter(datetime!(2024-01-15 12:00), datetime!(2024-01-15 11:00 UTC))]
+    #[case::summer(datetime!(2024-07-01 12:00), datetime!(2024-07-01 10:00 UTC))]
+    #[case::before_spring_gap(datetime!(2024-03-31 01:59:59), datetime!(2024-03-31 00:59:59 UTC))]
+    #[case::after_spring_gap(datetime!(2024-03-31 03:00), datetime!(2024-03-31 01:00 UTC))]
+    #[case::before_autumn_overlap(datetime!(2024-10-27 01:59:59), datetime!(2024-10-26 23:59:59 UTC))]
+    #[case::after_autumn_overlap(datetime!(2024-10-27 03:00), datetime!(2024-10-27 02:00 UTC))]
+    fn unique_times_are_converted(
+        #[case] local: PrimitiveDateTime,
+        #[case] utc: time::OffsetDateTime,
+    ) {
+        let datetime = UsosDateTime(local);
+
+        assert_eq!(datetime.resolve().unique(), Some(utc));
+        assert_eq!(datetime.to_utc(), utc);
+        assert_eq!(UsosDateTime::from_offset_datetime(utc), datetime);
+    }
+
+    #[test]
+    fn skipped_spring_hour_is_non_existent() {
+        let datetime = UsosDateTime(datetime!(2024-03-31 02:30));
+
+        assert_eq!(
+            datetime.resolve(),
+            WarsawDateTime::NonExistent {
+                shifted: datetime!(2024-03-31 03:30 +2)
+            }
+        );
+        assert_eq!(datetime.to_utc(), datetime!(2024-03-31 01:30 UTC));
+    }
+
+    #[test]
+    fn repeated_autumn_hour_is_ambiguous() {
+        let datetime = UsosDateTime(datetime!(2024-10-27 02:30));
+
+        assert_eq!(
+            datetime.resolve(),
+            WarsawDateTime::Ambiguous {
+                earlier: datetime!(2024-10-27 02:30 +2),
+                later: datetime!(2024-10-27 02:30 +1),
+            }
+        );
+        assert_eq!(datetime.to_utc(), datetime!(2024-10-27 00:30 UTC));
+        // both instants are displayed as the same local time
+        assert_eq!(
+            UsosDateTime::from_offset_datetime(datetime!(2024-10-27 00:30 UTC)),
+            datetime
+        );
+        assert_eq!(
+            UsosDateTime::from_offset_datetime(datetime!(2024-10-27 01:30 UTC)),
+            datetime
+        );
+    }
+
+    #[rstest]
+    #[case(2023, date!(2023-03-26), date!(2023-10-29))]
+    #[case(2024, date!(2024-03-31), date!(2024-10-27))]
+    #[case(2025, date!(2025-03-30), date!(2025-10-26))]
+    fn transitions_happen_on_last_sundays(
+        #[case] year: i32,
+        #[case] spring: Date,
+        #[case] autumn: Date,
+    ) {
+        assert_eq!(super::last_sunday(year, time::Month::March), spring);
+        assert_eq!(super::last_sunday(year, time::Month::October), autumn);
+        assert_eq!(
+            UsosDate(spring).to_offset_datetime().offset(),
+            STANDARD_OFFSET
+        );
+        assert_eq!(
+            UsosDate(spring.next_day().unwrap())
+                .to_offset_datetime()
+                .offset(),
+            DST_OFFSET
+        );
+    }
+
+    #[test]
+    fn precise_datetime_is_converted() {
+        let datetime = UsosPreciseDateTime(datetime!(2024-07-01 12:00:00.123456));
+
+        assert_eq!(datetime.to_utc(), datetime!(2024-07-01 10:00:00.123456 UTC));
+        assert_eq!(
+            UsosPreciseDateTime::from_offset_datetime(datetime.to_utc()),
+            datetime
+        );
+    }
 }
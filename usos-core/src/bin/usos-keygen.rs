@@ -0,0 +1,197 @@
+//! Command line tool for managing USOS API consumer keys.
+//!
+//! Every command that modifies resources on the USOS API server asks for confirmation first.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use clap::{Parser, Subcommand};
+use inquire::Confirm;
+use reqwest::Url;
+use secrecy::ExposeSecret;
+use usos_core::{
+    client::Client,
+    keys::{generation::CONSUMER_KEY_FILE_SUFFIX, ConsumerKey},
+};
+
+#[derive(Parser)]
+#[command(version, about = "Manage USOS API consumer keys")]
+struct Cli {
+    /// Base URL (origin) of the USOS API installation.
+    #[arg(long, default_value = "https://apps.usos.pwr.edu.pl/")]
+    base_url: Url,
+    /// Confirm all actions that modify resources on the server without asking.
+    #[arg(short, long)]
+    yes: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Register a new consumer key and save it to a file.
+    Generate {
+        /// Name of the application.
+        #[arg(long)]
+        app_name: String,
+        /// Developer's email address.
+        #[arg(long)]
+        email: String,
+        /// Website of the application.
+        #[arg(long)]
+        website_url: Option<String>,
+        /// Path of the file to save the key to. Defaults to a timestamped file in the current directory.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Test the key by calling `services/apisrv/consumer` after registering it.
+        #[arg(long)]
+        test: bool,
+    },
+    /// Test a saved key by calling `services/apisrv/consumer`.
+    Test {
+        /// Path of the key file.
+        key_file: PathBuf,
+    },
+    /// List keys saved in a directory.
+    List {
+        /// Directory to search in.
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Revoke a saved key.
+    Revoke {
+        /// Path of the key file.
+        key_file: PathBuf,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let base_url = with_trailing_slash(cli.base_url);
+
+    match cli.command {
+        Command::Generate {
+            app_name,
+            email,
+            website_url,
+            output,
+            test,
+        } => {
+            confirm(
+                cli.yes,
+                &format!("Register a new consumer key for '{app_name}' at {base_url}?"),
+            )?;
+            // the file is created before registering, as the secret cannot be retrieved if saving it fails
+            let path = output.unwrap_or_else(|| PathBuf::from(ConsumerKey::default_file_name()));
+            let mut file = ConsumerKey::create_file(&path)
+                .await
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+
+            let client = reqwest::Client::builder().cookie_store(true).build()?;
+            let key = match ConsumerKey::generate(
+                &client,
+                &base_url,
+                &app_name,
+                website_url.as_deref(),
+                &email,
+            )
+            .await
+            {
+                Ok(key) => key,
+                Err(e) => {
+                    drop(file);
+                    let _ = std::fs::remove_file(&path);
+                    return Err(e.into());
+                }
+            };
+            println!("Registered consumer key {}", key.key);
+
+            if let Err(e) = key.write_to_file(&mut file).await {
+                eprintln!(
+                    "Failed to save the key to {}: {e}\nSave the secret now, it cannot be retrieved later:\n{}",
+                    path.display(),
+                    key.secret.expose_secret()
+                );
+                bail!("Failed to save the key");
+            }
+            println!("Saved to {}", path.display());
+
+            if test {
+                test_key(&base_url, key).await?;
+            }
+        }
+        Command::Test { key_file } => {
+            test_key(&base_url, ConsumerKey::from_file(key_file)?).await?;
+        }
+        Command::List { dir } => list_keys(&dir)?,
+        Command::Revoke { key_file } => {
+            let key = ConsumerKey::from_file(&key_file)?;
+            confirm(
+                cli.yes,
+                &format!("Revoke consumer key {} at {base_url}?", key.key),
+            )?;
+            key.revoke(&base_url).await?;
+            println!("Revocation requested, check your email to complete it");
+        }
+    }
+
+    Ok(())
+}
+
+fn with_trailing_slash(mut url: Url) -> Url {
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    url
+}
+
+fn confirm(yes: bool, message: &str) -> anyhow::Result<()> {
+    if yes || Confirm::new(message).with_default(false).prompt()? {
+        Ok(())
+    } else {
+        bail!("Aborted")
+    }
+}
+
+async fn test_key(base_url: &Url, key: ConsumerKey) -> anyhow::Result<()> {
+    let consumer = Client::new(base_url.clone())
+        .authorized_from_key(key)
+        .builder("apisrv/consumer")
+        .payload(("fields", "name|email|date_registered"))
+        .request_json()
+        .await
+        .context("The key does not work")?;
+    println!("The key works: {consumer}");
+    Ok(())
+}
+
+fn list_keys(dir: &Path) -> anyhow::Result<()> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(CONSUMER_KEY_FILE_SUFFIX))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    if paths.is_empty() {
+        println!("No keys found in {}", dir.display());
+    }
+    for path in paths {
+        match ConsumerKey::from_file(&path) {
+            Ok(key) => println!(
+                "{}: {} ({})",
+                path.display(),
+                key.key,
+                key.owner.as_deref().unwrap_or("no owner")
+            ),
+            Err(e) => println!("{}: invalid key file - {e}", path.display()),
+        }
+    }
+
+    Ok(())
+}
@@ -12,15 +12,26 @@ use std::{
     fmt::{format, Debug},
     ops::Deref,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{anyhow, bail};
-use reqwest::{header::CONTENT_TYPE, IntoUrl, RequestBuilder, Response, StatusCode, Url};
+use reqwest::{
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    IntoUrl, RequestBuilder, Response, StatusCode, Url,
+};
 use serde::{Serialize, Serializer};
 use serde_json::{json, Value};
 
 use crate::{
-    api::{auth::AccessToken, errors::UsosError, oauth1::authorize, params::Params},
+    api::{
+        auth::{AccessToken, ScopeRecovery},
+        endpoint::{Endpoint, NotRequired},
+        errors::UsosError,
+        oauth1::authorize,
+        params::Params,
+        types::language::Language,
+    },
     errors::AppError,
     keys::ConsumerKey,
 };
@@ -30,6 +41,19 @@ pub struct Client {
     base_url: Url,
     client: reqwest::Client,
     auth: Option<ConsumerKey>,
+    scope_recovery: Option<ScopeRecoveryHook>,
+    timeout: Option<Duration>,
+    language: Language,
+}
+
+/// Shared handle to the [`ScopeRecovery`] hook of a [`Client`].
+#[derive(Clone)]
+struct ScopeRecoveryHook(Arc<dyn ScopeRecovery>);
+
+impl Debug for ScopeRecoveryHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ScopeRecoveryHook")
+    }
 }
 
 impl Client {
@@ -48,6 +72,9 @@ impl Client {
             base_url,
             client,
             auth: None,
+            scope_recovery: None,
+            timeout: None,
+            language: Language::English,
         }
     }
 
@@ -62,37 +89,117 @@ impl Client {
         self
     }
 
+    /// Sets the hook invoked when a call fails because the access token lacks some scopes.
+    ///
+    /// See [`ScopeRecovery`] for details.
+    pub fn with_scope_recovery(mut self, recovery: impl ScopeRecovery + 'static) -> Self {
+        self.scope_recovery = Some(ScopeRecoveryHook(Arc::new(recovery)));
+        self
+    }
+
+    /// Sets the timeout of every request, measured from sending the request until the response body is read.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the preferred language of the application user, English by default.
+    ///
+    /// The language belongs to this client only, so clients of users with different languages do not affect each other.
+    /// Localized texts are rendered in it by passing [`Client::language`] to e.g. [`LanguageDictionary::get`](crate::api::types::language::LanguageDictionary::get).
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
     pub fn builder(&self, uri: impl AsRef<str>) -> UsosRequestBuilder {
         UsosRequestBuilder::new(
-            &self.client,
+            self,
             self.base_url
                 .join("services/") // trailing slash is significant
                 .unwrap()
                 .join(uri.as_ref())
                 .unwrap(),
-            self.auth.clone(),
         )
     }
 
+    /// Calls the endpoint without an access token.
+    ///
+    /// Endpoints that require an access token are rejected at compile time, use [`Client::call_with_token`] for them.
+    /// Returns [`AppError::InvalidRequest`] if the endpoint requires a consumer key and the client has none.
+    pub async fn call<E>(&self, endpoint: &E) -> crate::Result<E::Response>
+    where
+        E: Endpoint,
+        E::Token: NotRequired,
+    {
+        self.endpoint_builder(endpoint)?
+            .request()
+            .await?
+            .json()
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Calls the endpoint on behalf of the user identified by the access token.
+    ///
+    /// Returns [`AppError::InvalidRequest`] if the client has no consumer key, as the token cannot be used without it.
+    pub async fn call_with_token<E: Endpoint>(
+        &self,
+        endpoint: &E,
+        token: &AccessToken,
+    ) -> crate::Result<E::Response> {
+        if self.auth.is_none() {
+            return Err(AppError::InvalidRequest(String::from(
+                "Access tokens can only be used by clients with a consumer key",
+            )));
+        }
+        self.endpoint_builder(endpoint)?
+            .auth(token)
+            .request()
+            .await?
+            .json()
+            .await
+            .map_err(Into::into)
+    }
+
+    fn endpoint_builder<E: Endpoint>(&self, endpoint: &E) -> crate::Result<UsosRequestBuilder> {
+        if E::CONSUMER.is_required() && self.auth.is_none() {
+            return Err(AppError::InvalidRequest(format!(
+                "{} requires a consumer key",
+                E::PATH
+            )));
+        }
+        Ok(self.builder(E::PATH).payload(endpoint.params()?))
+    }
+
     pub fn base_url(&self) -> &Url {
         &self.base_url
     }
+
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+
+    /// Whether the requests are signed with a consumer key.
+    pub fn is_authorized(&self) -> bool {
+        self.auth.is_some()
+    }
 }
 
 pub const CLIENT: LazyCell<Client> =
     LazyCell::new(|| Client::new(Url::parse("https://apps.usos.pwr.edu.pl").unwrap()));
 
 #[derive(Default)]
-struct Form<'a> {
+struct Form {
     payload: Option<BTreeMap<String, String>>,
-    auth: Option<(ConsumerKey, Option<&'a AccessToken>)>,
+    auth: Option<(ConsumerKey, Option<AccessToken>)>,
     payload_error: Option<serde_urlencoded::ser::Error>,
 }
 
-impl<'a> Form<'a> {
+impl Form {
     fn new(
         payload: Option<BTreeMap<String, String>>,
-        auth: Option<(ConsumerKey, Option<&'a AccessToken>)>,
+        auth: Option<(ConsumerKey, Option<AccessToken>)>,
     ) -> Self {
         Self {
             payload,
@@ -102,18 +209,22 @@ impl<'a> Form<'a> {
     }
 }
 
-pub struct UsosRequestBuilder<'a> {
-    request_builder: reqwest::RequestBuilder,
+pub struct UsosRequestBuilder {
+    client: reqwest::Client,
     uri: Url,
-    form: Form<'a>,
+    form: Form,
+    scope_recovery: Option<ScopeRecoveryHook>,
+    timeout: Option<Duration>,
 }
 
-impl<'a> UsosRequestBuilder<'a> {
-    fn new(client: &reqwest::Client, uri: Url, consumer_key: Option<ConsumerKey>) -> Self {
+impl UsosRequestBuilder {
+    fn new(client: &Client, uri: Url) -> Self {
         Self {
-            request_builder: client.post(uri.as_ref()),
+            client: client.client.clone(),
             uri,
-            form: Form::new(None, consumer_key.map(|key| (key, None))),
+            form: Form::new(None, client.auth.clone().map(|key| (key, None))),
+            scope_recovery: client.scope_recovery.clone(),
+            timeout: client.timeout,
         }
     }
 
@@ -123,62 +234,108 @@ impl<'a> UsosRequestBuilder<'a> {
     }
 
     /// ignores access token if consumer key was not set inside the client
-    pub fn auth(mut self, access_token: &'a AccessToken) -> Self {
+    pub fn auth(mut self, access_token: &AccessToken) -> Self {
         if let Some((consumer, access)) = &mut self.form.auth {
-            *access = Some(access_token);
+            *access = Some(access_token.clone());
         }
 
         self
     }
 
-    pub async fn request(mut self) -> Result<Response, AppError> {
-        if let Some(e) = self.form.payload_error {
-            // TODO: handle invalid form error
-            return Err(AppError::Unexpected(anyhow::anyhow!(e)));
+    /// Sends the request.
+    ///
+    /// If USOS API rejects the call because the access token lacks some scopes, the [`ScopeRecovery`] hook of the client
+    /// is invoked and the call is retried once with the token it returns. If there is no hook, the hook gives up,
+    /// or the retried call is still missing scopes, [`AppError::ScopeMissing`] is returned.
+    ///
+    /// The recovered token is not returned, so it has to be saved by the hook itself. Use
+    /// [`UsosRequestBuilder::request_with_recovered_token`] to get it instead.
+    pub async fn request(self) -> Result<Response, AppError> {
+        self.request_with_recovered_token()
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Same as [`UsosRequestBuilder::request`], but also returns the token the call succeeded with if the
+    /// [`ScopeRecovery`] hook replaced the one attached to the request, so that the caller can store it in place of
+    /// the old one. The token is [`None`] if the call succeeded with the original token.
+    pub async fn request_with_recovered_token(
+        mut self,
+    ) -> Result<(Response, Option<AccessToken>), AppError> {
+        if let Some(e) = self.form.payload_error.take() {
+            return Err(AppError::InvalidRequest(e.to_string()));
         }
 
-        let signed_form = match self.form.auth {
-            Some((consumer_key, token)) => authorize(
+        let token = self
+            .form
+            .auth
+            .as_ref()
+            .and_then(|(_, token)| token.as_ref());
+        let error = match self.send(token).await {
+            Err(error) => error,
+            Ok(response) => return Ok((response, None)),
+        };
+        let Some(missing) = error.missing_scopes() else {
+            return Err(error);
+        };
+
+        let new_token = match (&self.scope_recovery, token) {
+            (Some(ScopeRecoveryHook(recovery)), Some(token)) => {
+                recovery.recover(token.clone(), missing.clone()).await
+            }
+            _ => None,
+        };
+        let Some(new_token) = new_token else {
+            return Err(AppError::ScopeMissing { missing });
+        };
+
+        match self.send(Some(&new_token)).await {
+            Ok(response) => Ok((response, Some(new_token))),
+            Err(error) => Err(match error.missing_scopes() {
+                Some(missing) => AppError::ScopeMissing { missing },
+                None => error,
+            }),
+        }
+    }
+
+    async fn send(&self, token: Option<&AccessToken>) -> Result<Response, AppError> {
+        let signed_form = match &self.form.auth {
+            Some((consumer_key, _)) => authorize(
                 "POST",
                 self.uri.to_string(),
-                &consumer_key,
+                consumer_key,
                 token,
-                self.form.payload,
+                self.form.payload.clone(),
             ),
-            None => self.form.payload.unwrap_or_else(BTreeMap::new),
+            None => self.form.payload.clone().unwrap_or_default(),
         };
 
-        self.request_builder = self.request_builder.form(&signed_form);
-
-        let response = self.request_builder.send().await?;
-        let status = response.status();
-        if status.is_client_error() {
-            if status == StatusCode::NOT_FOUND {
-                return Err(AppError::http(status, None));
-            }
-            if status == StatusCode::UNAUTHORIZED {
-                println!("Unauthorized, token expired (session expired / user logged out / user revoked all tokens)");
-                return Err(AppError::http(status, None));
-            }
-            let error = response.json::<UsosError>().await?;
-            println!("{}", error);
-            return Err(AppError::http(status, Some(error)));
-        }
-        if status.is_server_error() {
-            println!("Internal server error");
-            return Err(AppError::http(status, None));
+        let mut request = self.client.post(self.uri.as_ref()).form(&signed_form);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
         }
 
-        if status.is_redirection() {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() || status.is_redirection() {
             return Ok(response);
         }
 
-        if status.is_informational() {
-            return Err(AppError::Unexpected(anyhow!(
+        match status {
+            StatusCode::UNAUTHORIZED => Err(AppError::Unauthorized),
+            StatusCode::NOT_FOUND => Err(AppError::NotFound),
+            StatusCode::TOO_MANY_REQUESTS => Err(AppError::RateLimited {
+                retry_after: retry_after(&response),
+            }),
+            status if status.is_server_error() => Err(AppError::Server { code: status }),
+            status if status.is_client_error() => {
+                let error = serde_json::from_slice::<UsosError>(&response.bytes().await?)?;
+                Err(AppError::usos(status, error))
+            }
+            _ => Err(AppError::Unexpected(anyhow!(
                 "Status codes 100-199 are unexpected"
-            )));
+            ))),
         }
-        return Ok(response);
     }
 
     pub async fn request_json(mut self) -> Result<Value, AppError> {
@@ -187,6 +344,12 @@ impl<'a> UsosRequestBuilder<'a> {
     }
 }
 
+/// Parses the `Retry-After` header given in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.parse().ok().map(Duration::from_secs)
+}
+
 #[tokio::test]
 async fn test_usos_client() {
     let client = Client::new(Url::parse("https://apps.usos.pw.edu.pl").unwrap());
@@ -214,6 +377,23 @@ fn relative_url_parts_joining_is_successful() {
     )
 }
 
+#[test]
+fn languages_of_clients_are_independent() {
+    use crate::api::types::language::LanguageDictionary;
+
+    let base = Url::parse("https://apps.usos.pwr.edu.pl").unwrap();
+    let polish = Client::new(base.clone()).with_language(Language::Polish);
+    let english = Client::new(base);
+    let name = LanguageDictionary::from_iter([
+        (Language::Polish, String::from("Wykład")),
+        (Language::English, String::from("Lecture")),
+    ]);
+
+    assert_eq!(name.get(polish.language()), "Wykład");
+    assert_eq!(name.get(english.language()), "Lecture");
+    assert_eq!(name.to_string(), "Lecture");
+}
+
 #[test]
 fn relative_url_parts_joining_is_failing() {
     let base = "https://apps.usos.pwr.edu.pl";
@@ -224,3 +404,239 @@ fn relative_url_parts_joining_is_failing() {
         Url::parse("https://apps.usos.pwr.edu.pl/apiref/method").unwrap()
     )
 }
+
+#[cfg(test)]
+use crate::api::{
+    errors::reason::Reason,
+    types::scopes::{Scope, Scopes},
+};
+#[cfg(test)]
+use wiremock::ResponseTemplate;
+
+#[cfg(test)]
+async fn scope_missing_server() -> wiremock::MockServer {
+    use wiremock::{
+        matchers::{body_string_contains, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("oauth_token=granted"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+            "message": "Your access token lacks some of the required scopes.",
+            "error": "method_forbidden",
+            "reason": "scope_missing",
+            "missing_scopes": ["grades"],
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[cfg(test)]
+fn test_token(token: &str) -> AccessToken {
+    AccessToken {
+        token: token.into(),
+        secret: String::from("secret").into(),
+    }
+}
+
+#[tokio::test]
+async fn scope_missing_call_is_retried_with_recovered_token() {
+    let server = scope_missing_server().await;
+    let client = Client::new(Url::parse(&server.uri()).unwrap())
+        .authorized_from_key(ConsumerKey::new(
+            "key".into(),
+            "secret".to_string().into(),
+            None,
+        ))
+        .with_scope_recovery(|_token, missing: Scopes| async move {
+            assert!(missing.contains(&Scope::Grades));
+            Some(test_token("granted"))
+        });
+
+    let token = test_token("expired");
+    let response = client
+        .builder("grades/terms2")
+        .auth(&token)
+        .request_json()
+        .await
+        .unwrap();
+
+    assert_eq!(response, json!({ "ok": true }));
+
+    let (_, recovered) = client
+        .builder("grades/terms2")
+        .auth(&token)
+        .request_with_recovered_token()
+        .await
+        .unwrap();
+
+    assert_eq!(recovered.unwrap().token, "granted");
+}
+
+#[tokio::test]
+async fn scope_missing_call_fails_when_recovery_gives_up() {
+    let server = scope_missing_server().await;
+    let client = Client::new(Url::parse(&server.uri()).unwrap())
+        .authorized_from_key(ConsumerKey::new(
+            "key".into(),
+            "secret".to_string().into(),
+            None,
+        ))
+        .with_scope_recovery(|_token, _missing| async { None });
+
+    let token = test_token("expired");
+    let error = client
+        .builder("grades/terms2")
+        .auth(&token)
+        .request()
+        .await
+        .unwrap_err();
+
+    match error {
+        AppError::ScopeMissing { missing } => {
+            assert_eq!(missing, Scopes::from_iter([Scope::Grades]))
+        }
+        other => panic!("Expected missing scopes error, got {other:?}"),
+    }
+}
+
+#[cfg(test)]
+#[rstest::rstest]
+#[case::unauthorized(ResponseTemplate::new(401), |e: &AppError| matches!(e, AppError::Unauthorized))]
+#[case::not_found(ResponseTemplate::new(404), |e: &AppError| matches!(e, AppError::NotFound))]
+#[case::rate_limited(
+    ResponseTemplate::new(429).insert_header("Retry-After", "30"),
+    |e: &AppError| matches!(e, AppError::RateLimited { retry_after: Some(d) } if d.as_secs() == 30)
+)]
+#[case::server(ResponseTemplate::new(503), |e: &AppError| matches!(e, AppError::Server { .. }))]
+#[case::forbidden(
+    ResponseTemplate::new(403).set_body_json(json!({
+        "message": "Only administrative consumers are allowed.",
+        "error": "method_forbidden",
+        "reason": "trusted_required",
+    })),
+    |e: &AppError| matches!(e, AppError::Forbidden { reason: Some(Reason::TrustedRequired), .. })
+)]
+#[case::usos(
+    ResponseTemplate::new(400).set_body_json(json!({
+        "message": "Required parameter fac_id is missing.",
+        "error": "param_missing",
+        "param_name": "fac_id",
+    })),
+    |e: &AppError| matches!(e, AppError::Usos { code: StatusCode::BAD_REQUEST, .. })
+)]
+#[case::decode(
+    ResponseTemplate::new(400).set_body_string("not json"),
+    |e: &AppError| matches!(e, AppError::Decode(_))
+)]
+#[tokio::test]
+async fn error_responses_are_classified(
+    #[case] response: ResponseTemplate,
+    #[case] is_expected: fn(&AppError) -> bool,
+) {
+    let server = wiremock::MockServer::start().await;
+    wiremock::Mock::given(wiremock::matchers::method("POST"))
+        .respond_with(response)
+        .mount(&server)
+        .await;
+
+    let error = Client::new(Url::parse(&server.uri()).unwrap())
+        .builder("users/user")
+        .request()
+        .await
+        .unwrap_err();
+
+    assert!(is_expected(&error), "unexpected error: {error:?}");
+}
+
+#[tokio::test]
+async fn connection_failure_is_retryable() {
+    let error = Client::new(Url::parse("http://127.0.0.1:1").unwrap())
+        .builder("users/user")
+        .request()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, AppError::Transport(_)));
+    assert!(error.is_retryable());
+    assert!(!error.is_auth_problem());
+}
+
+#[cfg(test)]
+mod endpoint_tests {
+    use serde::Deserialize;
+    use wiremock::{
+        matchers::{body_string_contains, method, path},
+        Mock, MockServer,
+    };
+
+    use super::*;
+    use crate::api::endpoint::{token, Requirement};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Term {
+        id: String,
+    }
+
+    struct GetTerm(&'static str);
+
+    impl Endpoint for GetTerm {
+        const PATH: &'static str = "terms/term";
+        const CONSUMER: Requirement = Requirement::Required;
+        type Token = token::Ignored;
+        type Response = Term;
+
+        fn params(&self) -> crate::Result<Params> {
+            Ok(Params::from(("term_id", self.0)))
+        }
+    }
+
+    fn client(server: &MockServer) -> Client {
+        Client::new(Url::parse(&server.uri()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn endpoint_response_is_decoded() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/services/terms/term"))
+            .and(body_string_contains("term_id=2024%2F25-Z"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "2024/25-Z" })))
+            .mount(&server)
+            .await;
+        let client = client(&server).authorized_from_key(ConsumerKey::new(
+            "key".into(),
+            "secret".to_string().into(),
+            None,
+        ));
+
+        let term = client.call(&GetTerm("2024/25-Z")).await.unwrap();
+
+        assert_eq!(
+            term,
+            Term {
+                id: String::from("2024/25-Z")
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_consumer_key_is_rejected_before_sending() {
+        let server = MockServer::start().await;
+
+        let error = client(&server)
+            .call(&GetTerm("2024/25-Z"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AppError::InvalidRequest(_)));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+}
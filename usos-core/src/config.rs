@@ -0,0 +1,481 @@
+//! Configuration file shared by applications and tools using USOS API.
+//!
+//! The configuration is stored in a TOML file (`usos.toml` by default) that defines named profiles, one per installation.
+//! Every profile contains the base URL of the installation, the consumer key and secret, the default language,
+//! the request timeout and the location of the token store. Values from the `[defaults]` table apply to every profile that does not set them.
+//!
+//! ```toml
+//! [defaults]
+//! language = "pl"
+//! timeout = 30 # seconds
+//!
+//! [profiles.pwr]
+//! base_url = "https://apps.usos.pwr.edu.pl/"
+//! consumer_key = "inline key"
+//! consumer_secret = { env = "PWR_CONSUMER_SECRET" }
+//! token_store = "tokens/pwr.json"
+//!
+//! [profiles.uw]
+//! base_url = "https://usosapps.uw.edu.pl/"
+//! consumer_key = { file = "/run/secrets/uw_key" }
+//! consumer_secret = { file = "/run/secrets/uw_secret" }
+//! language = "en"
+//! ```
+//!
+//! Relative paths (of `file` values and of the token store) are resolved against the directory of the configuration file.
+//!
+//! Any value of a profile can be overridden with an environment variable named `USOS_{PROFILE}_{KEY}`,
+//! where `PROFILE` is the upper-cased profile name, e.g. `USOS_PWR_CONSUMER_SECRET` or `USOS_UW_TIMEOUT`.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use reqwest::Url;
+use secrecy::SecretString;
+use serde::Deserialize;
+
+use crate::{
+    api::types::language::Language, client::Client, keys::ConsumerKey,
+    sessions::store::FileTokenStore,
+};
+
+/// Environment variable with the path of the configuration file.
+pub const CONFIG_PATH_VAR: &str = "USOS_CONFIG";
+/// Path of the configuration file used if [`CONFIG_PATH_VAR`] is not set.
+pub const DEFAULT_CONFIG_PATH: &str = "usos.toml";
+
+/// Contents of the configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    defaults: ProfileConfig,
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileConfig>,
+    /// Directory of the configuration file, relative paths in the file are resolved against it.
+    #[serde(skip)]
+    base_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileConfig {
+    base_url: Option<String>,
+    consumer_key: Option<ValueSource>,
+    consumer_secret: Option<ValueSource>,
+    consumer_email: Option<String>,
+    language: Option<Language>,
+    /// Request timeout in seconds.
+    timeout: Option<u64>,
+    token_store: Option<PathBuf>,
+}
+
+impl ProfileConfig {
+    /// Fills the missing values with the values from `other`.
+    fn or(self, other: &ProfileConfig) -> Self {
+        Self {
+            base_url: self.base_url.or_else(|| other.base_url.clone()),
+            consumer_key: self.consumer_key.or_else(|| other.consumer_key.clone()),
+            consumer_secret: self
+                .consumer_secret
+                .or_else(|| other.consumer_secret.clone()),
+            consumer_email: self.consumer_email.or_else(|| other.consumer_email.clone()),
+            language: self.language.or_else(|| other.language.clone()),
+            timeout: self.timeout.or(other.timeout),
+            token_store: self.token_store.or_else(|| other.token_store.clone()),
+        }
+    }
+
+    /// Resolves the relative paths against `dir`.
+    fn relative_to(self, dir: &Path) -> Self {
+        let source = |source: Option<ValueSource>| match source {
+            Some(ValueSource::File { file }) => Some(ValueSource::File {
+                file: dir.join(file),
+            }),
+            other => other,
+        };
+        Self {
+            consumer_key: source(self.consumer_key),
+            consumer_secret: source(self.consumer_secret),
+            token_store: self.token_store.map(|path| dir.join(path)),
+            ..self
+        }
+    }
+}
+
+/// Where to take a (possibly secret) value from.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum ValueSource {
+    /// Value written directly in the configuration file.
+    Inline(String),
+    /// Value of an environment variable.
+    Env { env: String },
+    /// Contents of a file, with surrounding whitespace trimmed.
+    File { file: PathBuf },
+}
+
+impl Debug for ValueSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inline(_) => write!(f, "Inline([REDACTED])"),
+            Self::Env { env } => f.debug_struct("Env").field("env", env).finish(),
+            Self::File { file } => f.debug_struct("File").field("file", file).finish(),
+        }
+    }
+}
+
+impl ValueSource {
+    fn resolve(&self, env: &impl Fn(&str) -> Option<String>) -> crate::Result<String> {
+        match self {
+            Self::Inline(value) => Ok(value.clone()),
+            Self::Env { env: name } => {
+                Ok(env(name).with_context(|| format!("Environment variable {name} is not set"))?)
+            }
+            Self::File { file } => Ok(std::fs::read_to_string(file)
+                .map(|value| value.trim().to_string())
+                .with_context(|| format!("Failed to read {}", file.display()))?),
+        }
+    }
+}
+
+/// Fully resolved configuration of a single installation.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub base_url: Url,
+    pub consumer_key: Option<ConsumerKey>,
+    pub language: Option<Language>,
+    pub timeout: Option<Duration>,
+    /// Path of the file with the access tokens, see [`Profile::token_store`].
+    pub token_store: Option<PathBuf>,
+}
+
+impl Profile {
+    /// Builds a client configured according to the profile.
+    pub fn client(&self) -> Client {
+        let mut client = Client::new(self.base_url.clone());
+        if let Some(consumer_key) = &self.consumer_key {
+            client = client.authorized_from_key(consumer_key.clone());
+        }
+        if let Some(language) = &self.language {
+            client = client.with_language(language.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.with_timeout(timeout);
+        }
+        client
+    }
+
+    /// Opens the token store of the profile, or returns [`None`] if the profile has none.
+    ///
+    /// The store can be used with [`UserSessions`](crate::sessions::UserSessions).
+    pub async fn token_store<K>(&self) -> crate::Result<Option<FileTokenStore<K>>>
+    where
+        K: serde::Serialize + serde::de::DeserializeOwned + Eq + std::hash::Hash,
+    {
+        match &self.token_store {
+            Some(path) => Ok(Some(FileTokenStore::open(path).await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl FromStr for Config {
+    type Err = toml::de::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s)
+    }
+}
+
+impl Config {
+    /// Loads the configuration from a file.
+    ///
+    /// Relative paths in the file are resolved against its directory. A configuration parsed from a string with
+    /// [`str::parse`] resolves them against the current directory instead.
+    pub fn load(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+
+        let config: Self = content
+            .parse()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        Ok(Self {
+            base_dir: path.parent().map(Path::to_path_buf),
+            ..config
+        })
+    }
+
+    /// Loads the configuration from the file pointed to by the `USOS_CONFIG` environment variable, or `usos.toml` if it is not set.
+    pub fn load_default() -> crate::Result<Self> {
+        let path = std::env::var(CONFIG_PATH_VAR).unwrap_or_else(|_| DEFAULT_CONFIG_PATH.into());
+        Self::load(path)
+    }
+
+    /// Names of all profiles defined in the configuration.
+    pub fn profile_names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// Resolves the profile, applying the defaults and the overrides from environment variables.
+    pub fn profile(&self, name: &str) -> crate::Result<Profile> {
+        self.profile_with_env(name, |var| std::env::var(var).ok())
+    }
+
+    fn profile_with_env(
+        &self,
+        name: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> crate::Result<Profile> {
+        let config = self
+            .profiles
+            .get(name)
+            .with_context(|| format!("Profile {name} is not defined"))?;
+        let (config, defaults) = match &self.base_dir {
+            Some(dir) => (
+                config.clone().relative_to(dir),
+                self.defaults.clone().relative_to(dir),
+            ),
+            None => (config.clone(), self.defaults.clone()),
+        };
+        let config = env_overrides(name, &env)?.or(&config).or(&defaults);
+
+        let base_url = config
+            .base_url
+            .with_context(|| format!("Profile {name} has no base_url"))?;
+        let base_url = Url::parse(&base_url)
+            .with_context(|| format!("Invalid base_url of profile {name}: {base_url}"))?;
+
+        let consumer_key = match (config.consumer_key, config.consumer_secret) {
+            (Some(key), Some(secret)) => Some(ConsumerKey::new(
+                key.resolve(&env)?,
+                SecretString::new(secret.resolve(&env)?),
+                config.consumer_email,
+            )),
+            (None, None) => None,
+            _ => {
+                return Err(anyhow!(
+                    "Profile {name} must define both consumer_key and consumer_secret"
+                )
+                .into())
+            }
+        };
+
+        Ok(Profile {
+            name: name.to_string(),
+            base_url,
+            consumer_key,
+            language: config.language,
+            timeout: config.timeout.map(Duration::from_secs),
+            token_store: config.token_store,
+        })
+    }
+}
+
+fn env_overrides(
+    profile: &str,
+    env: &impl Fn(&str) -> Option<String>,
+) -> crate::Result<ProfileConfig> {
+    let prefix = format!(
+        "USOS_{}_",
+        profile
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            })
+            .collect::<String>()
+    );
+    let var = |key: &str| env(&format!("{prefix}{key}"));
+
+    Ok(ProfileConfig {
+        base_url: var("BASE_URL"),
+        consumer_key: var("CONSUMER_KEY").map(ValueSource::Inline),
+        consumer_secret: var("CONSUMER_SECRET").map(ValueSource::Inline),
+        consumer_email: var("CONSUMER_EMAIL"),
+        language: var("LANGUAGE").map(Language::from),
+        timeout: var("TIMEOUT")
+            .map(|timeout| {
+                timeout
+                    .parse()
+                    .with_context(|| format!("Invalid timeout {prefix}TIMEOUT={timeout}"))
+            })
+            .transpose()?,
+        token_store: var("TOKEN_STORE").map(PathBuf::from),
+    })
+}
+
+impl Client {
+    /// Builds a client configured according to a profile from the default configuration file (see [`Config::load_default`]).
+    pub fn from_profile(name: &str) -> crate::Result<Self> {
+        Ok(Config::load_default()?.profile(name)?.client())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use secrecy::ExposeSecret;
+
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [defaults]
+        language = "pl"
+        timeout = 30
+
+        [profiles.pwr]
+        base_url = "https://apps.usos.pwr.edu.pl/"
+        consumer_key = "key"
+        consumer_secret = { env = "PWR_SECRET" }
+        token_store = "tokens/pwr.json"
+
+        [profiles.uw]
+        base_url = "https://usosapps.uw.edu.pl/"
+        language = "en"
+    "#;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn profile_is_resolved() {
+        let config: Config = CONFIG.parse().unwrap();
+        let profile = config
+            .profile_with_env("pwr", env(&[("PWR_SECRET", "secret")]))
+            .unwrap();
+
+        assert_eq!(profile.base_url.as_str(), "https://apps.usos.pwr.edu.pl/");
+        let consumer_key = profile.consumer_key.unwrap();
+        assert_eq!(consumer_key.key, "key");
+        assert_eq!(consumer_key.secret.expose_secret(), "secret");
+        assert_eq!(profile.language, Some(Language::Polish));
+        assert_eq!(profile.timeout, Some(Duration::from_secs(30)));
+        assert_eq!(profile.token_store, Some(PathBuf::from("tokens/pwr.json")));
+    }
+
+    #[test]
+    fn profile_values_override_defaults() {
+        let config: Config = CONFIG.parse().unwrap();
+        let profile = config.profile_with_env("uw", env(&[])).unwrap();
+
+        assert_eq!(profile.language, Some(Language::English));
+        assert_eq!(profile.timeout, Some(Duration::from_secs(30)));
+        assert!(profile.consumer_key.is_none());
+    }
+
+    #[test]
+    fn environment_overrides_profile() {
+        let config: Config = CONFIG.parse().unwrap();
+        let profile = config
+            .profile_with_env(
+                "pwr",
+                env(&[
+                    ("USOS_PWR_CONSUMER_SECRET", "env secret"),
+                    ("USOS_PWR_LANGUAGE", "en"),
+                    ("USOS_PWR_TIMEOUT", "5"),
+                ]),
+            )
+            .unwrap();
+
+        let consumer_key = profile.consumer_key.unwrap();
+        assert_eq!(consumer_key.key, "key");
+        assert_eq!(consumer_key.secret.expose_secret(), "env secret");
+        assert_eq!(profile.language, Some(Language::English));
+        assert_eq!(profile.timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn secret_is_read_from_file() {
+        let path = std::env::temp_dir().join(format!("usos_test_secret_{}", std::process::id()));
+        std::fs::write(&path, "file secret\n").unwrap();
+        let config: Config = format!(
+            r#"
+            [profiles.pwr]
+            base_url = "https://apps.usos.pwr.edu.pl/"
+            consumer_key = "key"
+            consumer_secret = {{ file = "{}" }}
+            "#,
+            path.display()
+        )
+        .parse()
+        .unwrap();
+
+        let profile = config.profile_with_env("pwr", env(&[]));
+        std::fs::remove_file(&path).unwrap();
+
+        let consumer_key = profile.unwrap().consumer_key.unwrap();
+        assert_eq!(consumer_key.secret.expose_secret(), "file secret");
+    }
+
+    #[tokio::test]
+    async fn paths_are_relative_to_config_file() {
+        let dir = std::env::temp_dir().join(format!("usos_test_config_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("secrets")).unwrap();
+        std::fs::write(dir.join("secrets/pwr"), "file secret\n").unwrap();
+        std::fs::write(
+            dir.join("usos.toml"),
+            r#"
+            [profiles.pwr]
+            base_url = "https://apps.usos.pwr.edu.pl/"
+            consumer_key = "key"
+            consumer_secret = { file = "secrets/pwr" }
+            token_store = "tokens.json"
+            "#,
+        )
+        .unwrap();
+
+        let profile = Config::load(dir.join("usos.toml"))
+            .unwrap()
+            .profile_with_env("pwr", env(&[]));
+        let store = match &profile {
+            Ok(profile) => Some(profile.token_store::<u32>().await),
+            Err(_) => None,
+        };
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let profile = profile.unwrap();
+        assert_eq!(
+            profile.consumer_key.unwrap().secret.expose_secret(),
+            "file secret"
+        );
+        assert_eq!(profile.token_store, Some(dir.join("tokens.json")));
+        assert_eq!(
+            store.unwrap().unwrap().unwrap().path(),
+            dir.join("tokens.json")
+        );
+    }
+
+    #[test]
+    fn missing_secret_variable_fails() {
+        let config: Config = CONFIG.parse().unwrap();
+        assert!(config.profile_with_env("pwr", env(&[])).is_err());
+    }
+
+    #[test]
+    fn unknown_profile_fails() {
+        let config: Config = CONFIG.parse().unwrap();
+        assert!(config.profile_with_env("uj", env(&[])).is_err());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let res = "[profiles.pwr]\nbase_uri = \"https://apps.usos.pwr.edu.pl/\"".parse::<Config>();
+        assert!(res.is_err());
+    }
+}
@@ -1,45 +1,174 @@
 //! Crate errors, including both USOS API errors and internal unexpected errors.
 
-use anyhow::anyhow;
+use std::time::Duration;
+
 use reqwest::StatusCode;
 use thiserror::Error;
 
-use crate::api::errors::UsosError;
+use crate::api::{
+    errors::{reason::Reason, UsosError},
+    types::scopes::Scopes,
+};
 
+/// Errors returned by the crate.
+///
+/// Use [`AppError::is_retryable`] and [`AppError::is_auth_problem`] to decide how to react to an error
+/// without matching on every variant.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum AppError {
-    /// Error returned from USOS API. See [`UsosError`] and [the USOS API reference](https://apps.usos.pw.edu.pl/developers/api/definitions/errors/).
-    #[error("Http error {code}")]
-    Http {
+    /// Failed to send the request or to receive the response, e.g. the connection could not be established or timed out.
+    #[error("Transport error: {0}")]
+    Transport(#[source] reqwest::Error),
+    /// The response body could not be decoded.
+    #[error("Failed to decode the response: {0}")]
+    Decode(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The request could not be built, e.g. the payload could not be serialized.
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    /// The access token has expired or has been revoked (session expired / user logged out / user revoked all tokens).
+    #[error("Unauthorized, the access token is invalid")]
+    Unauthorized,
+    /// Access to the method, one of its parameters or fields was denied.
+    #[error("Forbidden: {error}")]
+    Forbidden {
+        reason: Option<Reason>,
+        error: Box<UsosError>,
+    },
+    /// The called method does not exist.
+    #[error("Method not found")]
+    NotFound,
+    /// Too many requests were sent. Contains the delay requested by the server, if provided.
+    #[error("Rate limited")]
+    RateLimited { retry_after: Option<Duration> },
+    /// Internal server error of the USOS API installation.
+    #[error("Server error {code}")]
+    Server { code: StatusCode },
+    /// Any other error returned from USOS API. See [`UsosError`] and [the USOS API reference](https://apps.usos.pw.edu.pl/developers/api/definitions/errors/).
+    #[error("Http error {code}: {error}")]
+    Usos {
         code: StatusCode,
-        message: Option<UsosError>,
+        error: Box<UsosError>,
     },
+    /// The access token lacks some of the scopes required by the called method, and it could not be re-authorized.
+    /// See [`ScopeRecovery`](crate::api::auth::ScopeRecovery).
+    #[error("Access token is missing scopes: {missing}")]
+    ScopeMissing { missing: Scopes },
     /// Unexpected error caused by the crate or any of its dependencies.
     #[error(transparent)]
     Unexpected(#[from] anyhow::Error),
 }
 
 impl AppError {
-    /// Constructs an Http variant.
-    pub fn http(code: StatusCode, message: Option<UsosError>) -> Self {
-        Self::Http { code, message }
+    /// Constructs an error from the [`UsosError`] returned with a client error status code.
+    ///
+    /// `403 Forbidden` results in the `Forbidden` variant, any other code in the `Usos` variant.
+    pub fn usos(code: StatusCode, error: UsosError) -> Self {
+        if code == StatusCode::FORBIDDEN {
+            Self::Forbidden {
+                reason: error.reason().cloned(),
+                error: Box::new(error),
+            }
+        } else {
+            Self::Usos {
+                code,
+                error: Box::new(error),
+            }
+        }
     }
 
     /// Tries to extract [`UsosError`].
     ///
-    /// In case of the `Http` variant, returns the inner [`UsosError`] if it is present.
-    ///
-    /// Calling this method on the `Unexpected` variant always results in `None`.
+    /// Only the `Forbidden` and `Usos` variants contain [`UsosError`], for other variants `None` is returned.
     pub fn usos_error(&self) -> Option<&UsosError> {
         match self {
-            Self::Http { message, .. } => message.as_ref(),
+            Self::Forbidden { error, .. } | Self::Usos { error, .. } => Some(error),
             _ => None,
         }
     }
+
+    /// Returns the scopes that the access token lacks, if the error was caused by missing scopes.
+    pub fn missing_scopes(&self) -> Option<Scopes> {
+        match self {
+            Self::ScopeMissing { missing } => Some(missing.clone()),
+            _ => self
+                .usos_error()
+                .and_then(UsosError::missing_scopes)
+                .map(|scopes| scopes.iter().cloned().collect()),
+        }
+    }
+
+    /// Whether sending the same request again later may succeed.
+    ///
+    /// True for transport errors, rate limiting and server errors (except `501 Not Implemented`).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(_) | Self::RateLimited { .. } => true,
+            Self::Server { code } => *code != StatusCode::NOT_IMPLEMENTED,
+            _ => false,
+        }
+    }
+
+    /// Whether the error was caused by missing, invalid or insufficient credentials (consumer key or access token).
+    ///
+    /// Such errors can only be solved by authorizing the request differently, e.g. by obtaining a new access token.
+    pub fn is_auth_problem(&self) -> bool {
+        match self {
+            Self::Unauthorized | Self::ScopeMissing { .. } => true,
+            Self::Forbidden {
+                reason: Some(reason),
+                ..
+            } => matches!(
+                reason,
+                Reason::ConsumerMissing
+                    | Reason::UserMissing
+                    | Reason::TrustedRequired
+                    | Reason::ScopeMissing
+                    | Reason::ImpersonateRequired
+            ),
+            _ => false,
+        }
+    }
 }
 
 impl From<reqwest::Error> for AppError {
     fn from(value: reqwest::Error) -> Self {
-        Self::Unexpected(anyhow!(value))
+        if value.is_decode() {
+            Self::Decode(Box::new(value))
+        } else if value.is_builder() {
+            Self::InvalidRequest(value.to_string())
+        } else {
+            Self::Transport(value)
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Decode(Box::new(value))
     }
 }
+
+/// Errors that can occur while registering a consumer key (see [`ConsumerKey::generate`](crate::keys::ConsumerKey::generate)).
+#[cfg(feature = "keygen")]
+#[derive(Error, Debug)]
+pub enum KeygenError {
+    /// The base URL is not a valid HTTP(S) origin.
+    #[error("Invalid USOS API origin: {0}")]
+    InvalidOrigin(String),
+    /// The developers page did not set the `csrftoken` cookie required to submit the registration form.
+    #[error("CSRF token cookie (csrftoken) was not set by the developers page")]
+    CsrfCookieMissing,
+    /// USOS rejected the registration form. Contains validation messages for each form field.
+    #[error("Registration form is invalid: {0:?}")]
+    FormValidation(std::collections::BTreeMap<String, Vec<String>>),
+    /// USOS rejected the registration for a reason other than form validation.
+    #[error("Registration rejected (http status {code}, registration status {status:?})")]
+    RegistrationRejected {
+        code: StatusCode,
+        status: Option<String>,
+    },
+    /// Failed to communicate with USOS.
+    #[error(transparent)]
+    Transport(#[from] reqwest::Error),
+}
@@ -3,7 +3,7 @@
 #[cfg(feature = "keygen")]
 pub mod generation;
 
-use std::{env::VarError, ops::Deref, path::Path, sync::Arc};
+use std::{collections::HashMap, env::VarError, ops::Deref, path::Path, sync::Arc};
 
 use anyhow::Context;
 use reqwest::{
@@ -75,4 +75,26 @@ impl ConsumerKey {
             }),
         })
     }
+
+    /// Constructs `ConsumerKey` from a file with environment variables, such as the one written by `save_to_file`.
+    ///
+    /// The variable names are the same as in [`ConsumerKey::from_env`]. The environment of the process is not modified.
+    pub fn from_file(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let mut vars = dotenvy::from_path_iter(path)
+            .and_then(|vars| vars.collect::<Result<HashMap<_, _>, _>>())
+            .with_context(|| format!("Failed to read consumer key file {}", path.display()))?;
+
+        let key = vars
+            .remove(CONSUMER_KEY_NAME)
+            .with_context(|| format!("{CONSUMER_KEY_NAME} is missing in {}", path.display()))?;
+        let secret = vars
+            .remove(CONSUMER_SECRET_NAME)
+            .with_context(|| format!("{CONSUMER_SECRET_NAME} is missing in {}", path.display()))?;
+        let owner = vars
+            .remove(CONSUMER_KEY_OWNER)
+            .filter(|owner| !owner.is_empty());
+
+        Ok(Self::new(key, secret.into(), owner))
+    }
 }
@@ -1,24 +1,28 @@
-use std::{env::VarError, path::Path, sync::Arc};
+use std::{collections::BTreeMap, env::VarError, path::Path, sync::Arc};
 
 use anyhow::Context;
 use reqwest::{
     header::{COOKIE, HOST, ORIGIN, REFERER},
-    Url,
+    StatusCode, Url,
 };
 use secrecy::{ExposeSecret, Secret, SecretString};
 use serde::{Deserialize, Serialize};
 use time::macros::format_description;
+use tokio::io::AsyncWriteExt;
 
-use crate::{api::errors::UsosError, errors::AppError};
+use crate::{
+    api::errors::UsosError,
+    errors::{AppError, KeygenError},
+};
 
 use super::{
     ConsumerKey, ConsumerKeyRef, CONSUMER_KEY_NAME, CONSUMER_KEY_OWNER, CONSUMER_SECRET_NAME,
 };
 
-impl ConsumerKey {
-    // TODO: either create an error type dedicated for keygen scripts, or allow the function to panic on many occasions related to user input
-    // TODO: consider creating a CLI tool for generating keys that uses this code underneath.
+/// Suffix of the names of files written by [`ConsumerKey::save_to_file`].
+pub const CONSUMER_KEY_FILE_SUFFIX: &str = "_consumer_key.env";
 
+impl ConsumerKey {
     /// Registers a new consumer key with the USOS API.
     ///
     /// IMPORTANT: This function performs calls to USOS API that modify your resources.
@@ -30,7 +34,7 @@ impl ConsumerKey {
     /// # Arguments
     ///
     /// * `client` - The `reqwest::Client` used to make the HTTP requests.
-    /// * `base_url` - The base URL (origin) of the USOS API **with trailing slash** (example: <https://apps.usos.pwr.edu.pl/>).
+    /// * `base_url` - The base URL (origin) of the USOS API (example: <https://apps.usos.pwr.edu.pl/>). A missing trailing slash is added.
     /// * `app_name` - The name of the application to register.
     /// * `website_url` - An optional website URL associated with the application.
     /// * `email` - The developer's email address used for registration.
@@ -38,73 +42,75 @@ impl ConsumerKey {
     /// # Returns
     ///
     /// Returns `Ok(Self)` with a newly registered `ConsumerKey` upon successful registration.
-    /// If the registration fails due to a client or server error, an `AppError::Unexpected` error is returned.
     ///
     /// # Errors
     ///
-    /// This function depends on USOS API and therefore can fail due to the api error, in which case an unexpected [`AppError`] is returned.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the `base_url` does not end with a trailing slash or isn't a valid HTTP origin.
+    /// See [`KeygenError`] for the possible failures.
     pub async fn generate(
         client: &reqwest::Client,
         base_url: &Url,
         app_name: &str,
         website_url: Option<&str>,
         email: &str,
-    ) -> crate::Result<Self> {
+    ) -> Result<Self, KeygenError> {
+        let base_url = normalize_base_url(base_url)?;
+        let developers_url = join_url(&base_url, "developers")?;
+        let submit_url = join_url(&base_url, "developers/submit")?;
+
         let form = RegistrationForm::new(app_name, website_url, email);
-        let response = client
-            .get(base_url.join("developers").unwrap())
-            .send()
-            .await?;
+        let response = client.get(developers_url.clone()).send().await?;
 
-        let csrf_token_cookie = response
+        let csrf_token = response
             .cookies()
-            .next()
-            .context("Csrf token cookie expected but not found")?;
+            .find(|cookie| cookie.name() == CSRF_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_string())
+            .ok_or(KeygenError::CsrfCookieMissing)?;
 
         let response = client
-            .post(base_url.join("developers/submit").unwrap())
-            .header(COOKIE, &format!("csrftoken={}", csrf_token_cookie.value()))
-            .header(HOST, base_url.host_str().unwrap())
-            .header(ORIGIN, base_url.as_str())
-            .header(REFERER, base_url.join("developers").unwrap().as_str())
-            .header("X-CSRFToken", csrf_token_cookie.value())
+            .post(submit_url)
+            .header(COOKIE, format!("{CSRF_COOKIE_NAME}={csrf_token}"))
+            .header(HOST, host_header(&base_url)?)
+            .header(ORIGIN, base_url.origin().ascii_serialization())
+            .header(REFERER, developers_url.as_str())
+            .header("X-CSRFToken", &csrf_token)
             .form(&form)
             .send()
             .await?;
 
-        if response.status().is_client_error() || response.status().is_server_error() {
-            return Err(AppError::Unexpected(anyhow::anyhow!(
-                "Registering the consumer key failed. Response from USOS: {response:#?}"
-            )));
-        }
+        let code = response.status();
+        let body = response.bytes().await?;
+        let reg = serde_json::from_slice::<RegistrationResponse>(&body).ok();
 
-        let reg: RegistrationResponse = response.json().await?;
-        if reg.status != "success" {
-            return Err(AppError::Unexpected(anyhow::anyhow!(
-                "Registering the consumer key failed. Registration status from USOS: {:#?}",
-                reg.status
-            )));
+        if let Some(errors) = reg.as_ref().and_then(RegistrationResponse::form_errors) {
+            return Err(KeygenError::FormValidation(errors));
         }
 
-        return Ok(Self {
-            inner: Arc::new(ConsumerKeyRef {
-                key: reg.consumer_key,
-                secret: reg.consumer_secret,
-                owner: Some(email.into()),
+        match reg {
+            Some(RegistrationResponse {
+                status,
+                consumer_key: Some(key),
+                consumer_secret: Some(secret),
+                ..
+            }) if code.is_success() && status == "success" => Ok(Self {
+                inner: Arc::new(ConsumerKeyRef {
+                    key,
+                    secret,
+                    owner: Some(email.into()),
+                }),
+            }),
+            reg => Err(KeygenError::RegistrationRejected {
+                code,
+                status: reg.map(|reg| reg.status),
             }),
-        });
+        }
     }
 
-    /// Saves the consumer key information to a `.env` file.
+    /// Saves the consumer key information to a `.env` file at the given path.
     ///
     /// IMPORTANT: This call exposes the consumer secret and writes it to the file.
     ///
     /// This function writes the consumer key, consumer secret, and optionally the owner's email
-    /// to a file in the current directory.
+    /// to a new file. It fails if the file already exists. On Unix, the file is readable and writable only by its owner.
     ///
     /// The file contents will be formatted as environment variables:
     ///
@@ -117,23 +123,49 @@ impl ConsumerKey {
     /// # USOS_CONSUMER_EMAIL=
     /// ```
     ///
-    /// Example target file name: `2024-09-03_11-50_consumer_key.env`
-    pub async fn save_to_file(&self) -> Result<(), tokio::io::Error> {
-        tokio::fs::write(
-            Path::new(&format!(
-                "./{}_consumer_key.env",
-                time::OffsetDateTime::now_utc()
-                    .format(format_description!("[year]-[month]-[day]_[hour]-[minute]"))
-                    .unwrap()
-            )),
+    /// See [`ConsumerKey::default_file_name`] for a suggested file name. The file can be read back with [`ConsumerKey::from_file`].
+    pub async fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), tokio::io::Error> {
+        let mut file = Self::create_file(path).await?;
+        self.write_to_file(&mut file).await
+    }
+
+    /// Creates a new key file the way [`ConsumerKey::save_to_file`] does, without writing to it.
+    ///
+    /// Useful for checking that the key can be saved before registering it, as the secret cannot be retrieved later.
+    pub async fn create_file(path: impl AsRef<Path>) -> Result<tokio::fs::File, tokio::io::Error> {
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        options.open(path).await
+    }
+
+    /// Writes the key to a file created with [`ConsumerKey::create_file`], in the format of [`ConsumerKey::save_to_file`].
+    pub async fn write_to_file(&self, file: &mut tokio::fs::File) -> Result<(), tokio::io::Error> {
+        file.write_all(
             format!(
                 "{CONSUMER_KEY_NAME}={}\n{CONSUMER_SECRET_NAME}={}\n{CONSUMER_KEY_OWNER}={}\n",
                 self.key,
                 self.secret.expose_secret(),
                 self.owner.as_deref().unwrap_or_default(),
-            ),
+            )
+            .as_bytes(),
+        )
+        .await?;
+        file.flush().await
+    }
+
+    /// File name for saving the consumer key, based on the current time.
+    ///
+    /// Example file name: `2024-09-03_11-50_consumer_key.env`
+    pub fn default_file_name() -> String {
+        format!(
+            "{}{CONSUMER_KEY_FILE_SUFFIX}",
+            time::OffsetDateTime::now_utc()
+                .format(format_description!("[year]-[month]-[day]_[hour]-[minute]"))
+                .unwrap()
         )
-        .await
     }
 
     /// Revokes the consumer key with the USOS API.
@@ -148,9 +180,11 @@ impl ConsumerKey {
     ///
     /// # Arguments
     ///
-    /// * `base_url` - The base URL (origin) of the USOS API, **with a trailing slash**.
+    /// * `base_url` - The base URL (origin) of the USOS API. A missing trailing slash is added.
     pub async fn revoke(self, base_url: &Url) -> crate::Result<()> {
-        let url = base_url.join("services/oauth/revoke_consumer_key").unwrap();
+        let url = normalize_base_url(base_url)
+            .and_then(|base_url| join_url(&base_url, "services/oauth/revoke_consumer_key"))
+            .map_err(|e| AppError::Unexpected(anyhow::anyhow!(e)))?;
 
         let response = reqwest::Client::new()
             .post(url)
@@ -166,10 +200,7 @@ impl ConsumerKey {
         if status_code.is_client_error() || status_code.is_server_error() {
             let error: UsosError = response.json().await?;
 
-            return Err(AppError::Http {
-                code: status_code,
-                message: Some(error),
-            });
+            return Err(AppError::usos(status_code, error));
         }
 
         Ok(())
@@ -202,20 +233,241 @@ impl RegistrationForm {
 #[derive(Deserialize)]
 struct RegistrationResponse {
     status: String,
-    consumer_key: String,
-    consumer_secret: SecretString,
+    consumer_key: Option<String>,
+    consumer_secret: Option<SecretString>,
+    /// Validation messages for each form field. A field can have either a single message or a list of them.
+    #[serde(default)]
+    errors: BTreeMap<String, FieldErrors>,
 }
 
 impl RegistrationResponse {
-    fn new(
-        status: impl Into<String>,
-        client_id: impl Into<String>,
-        client_secret: impl Into<SecretString>,
-    ) -> Self {
-        Self {
-            status: status.into(),
-            consumer_key: client_id.into(),
-            consumer_secret: client_secret.into(),
+    fn form_errors(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        if self.errors.is_empty() {
+            return None;
         }
+
+        Some(
+            self.errors
+                .iter()
+                .map(|(field, errors)| {
+                    let messages = match errors {
+                        FieldErrors::One(message) => vec![message.clone()],
+                        FieldErrors::Many(messages) => messages.clone(),
+                    };
+                    (field.clone(), messages)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FieldErrors {
+    One(String),
+    Many(Vec<String>),
+}
+
+const CSRF_COOKIE_NAME: &str = "csrftoken";
+
+/// Checks that the URL is an HTTP(S) origin and makes sure that its path ends with a slash, so that it can be joined with relative paths.
+fn normalize_base_url(base_url: &Url) -> Result<Url, KeygenError> {
+    if !matches!(base_url.scheme(), "http" | "https") || base_url.host_str().is_none() {
+        return Err(KeygenError::InvalidOrigin(base_url.to_string()));
+    }
+
+    let mut url = base_url.clone();
+    url.set_query(None);
+    url.set_fragment(None);
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+
+    Ok(url)
+}
+
+fn join_url(base_url: &Url, path: &str) -> Result<Url, KeygenError> {
+    base_url
+        .join(path)
+        .map_err(|_| KeygenError::InvalidOrigin(base_url.to_string()))
+}
+
+fn host_header(base_url: &Url) -> Result<String, KeygenError> {
+    let host = base_url
+        .host_str()
+        .ok_or_else(|| KeygenError::InvalidOrigin(base_url.to_string()))?;
+
+    Ok(match base_url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    async fn developers_stub(csrf_cookie: &str, submit_response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/developers"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("set-cookie", "sessionid=session; Path=/")
+                    .append_header("set-cookie", format!("{csrf_cookie}=token; Path=/")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/developers/submit"))
+            .and(header("x-csrftoken", "token"))
+            .and(header("cookie", "csrftoken=token"))
+            .respond_with(submit_response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn generate(server: &MockServer) -> Result<ConsumerKey, KeygenError> {
+        ConsumerKey::generate(
+            &reqwest::Client::new(),
+            &Url::parse(&server.uri()).unwrap(),
+            "app",
+            None,
+            "dev@example.com",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn registration_is_successful() {
+        let server = developers_stub(
+            "csrftoken",
+            ResponseTemplate::new(200).set_body_json(json!({
+                "status": "success",
+                "consumer_key": "key",
+                "consumer_secret": "secret",
+            })),
+        )
+        .await;
+
+        let key = generate(&server).await.unwrap();
+
+        assert_eq!(key.key, "key");
+        assert_eq!(key.secret.expose_secret(), "secret");
+        assert_eq!(key.owner.as_deref(), Some("dev@example.com"));
+    }
+
+    #[tokio::test]
+    async fn registration_without_csrf_cookie_fails() {
+        let server = developers_stub("othertoken", ResponseTemplate::new(200)).await;
+
+        let res = generate(&server).await;
+
+        assert!(matches!(res, Err(KeygenError::CsrfCookieMissing)));
+    }
+
+    #[tokio::test]
+    async fn registration_form_errors_are_parsed() {
+        let server = developers_stub(
+            "csrftoken",
+            ResponseTemplate::new(400).set_body_json(json!({
+                "status": "fail",
+                "errors": {
+                    "email": "Enter a valid email address.",
+                    "appname": ["This field is required.", "Too short."],
+                },
+            })),
+        )
+        .await;
+
+        let res = generate(&server).await;
+
+        let Err(KeygenError::FormValidation(errors)) = res else {
+            panic!("Expected form validation error, got {res:?}");
+        };
+        assert_eq!(errors["email"], vec!["Enter a valid email address."]);
+        assert_eq!(errors["appname"].len(), 2);
+    }
+
+    #[tokio::test]
+    async fn registration_rejected_by_status() {
+        let server = developers_stub(
+            "csrftoken",
+            ResponseTemplate::new(200).set_body_json(json!({ "status": "fail" })),
+        )
+        .await;
+
+        let res = generate(&server).await;
+
+        assert!(matches!(
+            res,
+            Err(KeygenError::RegistrationRejected { code, status: Some(status) })
+                if code == StatusCode::OK && status == "fail"
+        ));
+    }
+
+    #[tokio::test]
+    async fn registration_rejected_by_http_error() {
+        let server = developers_stub("csrftoken", ResponseTemplate::new(500)).await;
+
+        let res = generate(&server).await;
+
+        assert!(matches!(
+            res,
+            Err(KeygenError::RegistrationRejected { code, status: None })
+                if code == StatusCode::INTERNAL_SERVER_ERROR
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case("https://apps.usos.pwr.edu.pl", "https://apps.usos.pwr.edu.pl/")]
+    #[case("https://apps.usos.pwr.edu.pl/", "https://apps.usos.pwr.edu.pl/")]
+    #[case("https://example.com/usos?a=b", "https://example.com/usos/")]
+    #[case("http://127.0.0.1:8080/usos/", "http://127.0.0.1:8080/usos/")]
+    fn base_url_is_normalized(#[case] base_url: &str, #[case] expected: &str) {
+        let url = normalize_base_url(&Url::parse(base_url).unwrap()).unwrap();
+        assert_eq!(url.as_str(), expected);
+    }
+
+    #[rstest::rstest]
+    #[case("mailto:dev@example.com")]
+    #[case("file:///tmp/usos")]
+    fn invalid_base_url_is_rejected(#[case] base_url: &str) {
+        let res = normalize_base_url(&Url::parse(base_url).unwrap());
+        assert!(matches!(res, Err(KeygenError::InvalidOrigin(_))));
+    }
+
+    #[tokio::test]
+    async fn saved_key_can_be_read_back() {
+        let path = std::env::temp_dir().join(format!(
+            "usos_test_{}{CONSUMER_KEY_FILE_SUFFIX}",
+            std::process::id()
+        ));
+        let key = ConsumerKey::new("key".into(), String::from("secret").into(), None);
+
+        key.save_to_file(&path).await.unwrap();
+        let read = ConsumerKey::from_file(&path);
+        let overwrite = key.save_to_file(&path).await;
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::metadata(&path).unwrap().permissions().mode()
+        };
+        std::fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.key, "key");
+        assert_eq!(read.secret.expose_secret(), "secret");
+        assert_eq!(read.owner, None);
+        assert!(overwrite.is_err());
+        #[cfg(unix)]
+        assert_eq!(mode & 0o777, 0o600);
     }
 }
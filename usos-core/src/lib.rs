@@ -7,15 +7,20 @@
 //!
 //! Name | Description | Default
 //! --- | --- | ---
-//! `keygen` | Enables consumer key generation API (see [`client`]) | No
+//! `keygen` | Enables consumer key generation API (see [`client`]) and the `usos-keygen` binary | No
 
 #![cfg_attr(debug_assertions, allow(unused))]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 
+// lets the code generated by `usos-macros` refer to `::usos_core` inside this crate
+extern crate self as usos_core;
+
 pub mod api;
 pub mod client;
+pub mod config;
 pub mod errors;
 pub mod keys;
+pub mod sessions;
 
 // should stay in projecet root, see issue https://github.com/time-rs/time/issues/597
 time::serde::format_description!(date_string, Date, api::types::time::DATE_FORMAT);
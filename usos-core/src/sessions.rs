@@ -0,0 +1,368 @@
+//! Access token management for applications acting on behalf of many users at once.
+//!
+//! A server-side application usually holds access tokens of many students. [`UserSessions`] maps the application's own
+//! user identifiers to their [`AccessToken`]s kept in a [`TokenStore`], hands out request builders with the right token attached,
+//! applies a per-user [`RateLimit`] and evicts tokens that USOS API reports as expired or revoked.
+
+pub mod store;
+
+use std::{collections::HashMap, hash::Hash, sync::Arc, time::Duration};
+
+use reqwest::{Response, StatusCode};
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::{
+    api::{auth::AccessToken, params::Params},
+    client::{Client, UsosRequestBuilder},
+    errors::AppError,
+};
+
+pub use store::{FileTokenStore, MemoryTokenStore, TokenStore};
+
+/// Maximum number of requests a single user can make in a given period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    requests: u32,
+    period: Duration,
+}
+
+impl RateLimit {
+    /// Allows `requests` requests per `period`, evenly spaced, with bursts of up to `requests` requests.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is zero.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "Rate limit must allow at least one request");
+        Self { requests, period }
+    }
+
+    fn interval(&self) -> Duration {
+        self.period / self.requests
+    }
+}
+
+/// Per-user rate limiter based on the generic cell rate algorithm.
+///
+/// For every user it remembers the theoretical arrival time of the next request.
+#[derive(Debug)]
+struct Limiter<K> {
+    limit: RateLimit,
+    arrivals: std::sync::Mutex<HashMap<K, Instant>>,
+}
+
+impl<K: Eq + Hash + Clone> Limiter<K> {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            arrivals: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserves a slot for a request of the user, returning the time to wait before sending it.
+    fn reserve(&self, user: &K, now: Instant) -> Duration {
+        let mut arrivals = self.arrivals.lock().unwrap();
+        if !arrivals.contains_key(user) {
+            arrivals.retain(|_, arrival| *arrival > now);
+        }
+
+        let interval = self.limit.interval();
+        let tolerance = self.limit.period.saturating_sub(interval);
+        let arrival = arrivals.get(user).copied().unwrap_or(now).max(now);
+        arrivals.insert(user.clone(), arrival + interval);
+
+        (arrival - now).saturating_sub(tolerance)
+    }
+}
+
+/// Access tokens of many application users, shared across tasks.
+///
+/// Cloning this struct is cheap, because it contains an inner `Arc`.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn example(client: usos_core::client::Client, token: usos_core::api::auth::AccessToken) -> usos_core::Result<()> {
+/// use std::time::Duration;
+/// use usos_core::sessions::{MemoryTokenStore, RateLimit, UserSessions};
+///
+/// let sessions = UserSessions::new(client, MemoryTokenStore::new())
+///     .with_rate_limit(RateLimit::new(10, Duration::from_secs(1)));
+/// sessions.insert_token(42_u64, token).await?;
+///
+/// if let Some(builder) = sessions.builder(&42, "users/user").await? {
+///     let user = builder.payload(("fields", "id|first_name")).request_json().await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct UserSessions<K, S> {
+    inner: Arc<SessionsInner<K, S>>,
+}
+
+struct SessionsInner<K, S> {
+    client: Client,
+    store: S,
+    limiter: Option<Limiter<K>>,
+}
+
+impl<K, S> Clone for UserSessions<K, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K, S> UserSessions<K, S>
+where
+    K: Eq + Hash + Clone + Send + Sync,
+    S: TokenStore<K>,
+{
+    pub fn new(client: Client, store: S) -> Self {
+        Self {
+            inner: Arc::new(SessionsInner {
+                client,
+                store,
+                limiter: None,
+            }),
+        }
+    }
+
+    /// Sets the rate limit applied to the requests of every user separately.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sessions have already been cloned.
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        Arc::get_mut(&mut self.inner)
+            .expect("Rate limit must be set before the sessions are shared")
+            .limiter = Some(Limiter::new(limit));
+        self
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.inner.client
+    }
+
+    pub fn store(&self) -> &S {
+        &self.inner.store
+    }
+
+    /// Saves the access token of the user, e.g. after the user has completed the OAuth flow.
+    pub async fn insert_token(&self, user: K, token: AccessToken) -> crate::Result<()> {
+        self.inner.store.insert(user, token).await
+    }
+
+    /// Removes the access token of the user, e.g. when the user logs out.
+    pub async fn remove_token(&self, user: &K) -> crate::Result<Option<AccessToken>> {
+        self.inner.store.remove(user).await
+    }
+
+    /// Creates a request builder with the access token of the user attached.
+    ///
+    /// Returns `None` if there is no token stored for the user, which means that the user has to be authorized first.
+    pub async fn builder(
+        &self,
+        user: &K,
+        uri: impl AsRef<str>,
+    ) -> crate::Result<Option<UserRequestBuilder<K, S>>> {
+        let Some(token) = self.inner.store.get(user).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(UserRequestBuilder {
+            builder: self.inner.client.builder(uri).auth(&token),
+            sessions: self.clone(),
+            user: user.clone(),
+        }))
+    }
+}
+
+/// [`UsosRequestBuilder`] bound to a single user of [`UserSessions`].
+pub struct UserRequestBuilder<K, S> {
+    builder: UsosRequestBuilder,
+    sessions: UserSessions<K, S>,
+    user: K,
+}
+
+impl<K, S> UserRequestBuilder<K, S>
+where
+    K: Eq + Hash + Clone + Send + Sync,
+    S: TokenStore<K>,
+{
+    pub fn payload<T: Into<Params>>(mut self, payload: T) -> Self {
+        self.builder = self.builder.payload(payload);
+        self
+    }
+
+    /// Sends the request, waiting for the rate limit of the user first.
+    ///
+    /// If USOS API responds with `401 Unauthorized`, the token of the user has expired or has been revoked,
+    /// so it is removed from the store before the error is returned.
+    /// If the [`ScopeRecovery`](crate::api::auth::ScopeRecovery) hook of the client replaced the token,
+    /// the new token is saved in the store.
+    pub async fn request(self) -> crate::Result<Response> {
+        let inner = &self.sessions.inner;
+        if let Some(limiter) = &inner.limiter {
+            let wait = limiter.reserve(&self.user, Instant::now());
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        match self.builder.request_with_recovered_token().await {
+            Ok((response, recovered)) => {
+                if let Some(token) = recovered {
+                    inner.store.insert(self.user, token).await?;
+                }
+                Ok(response)
+            }
+            Err(AppError::Unauthorized) => {
+                inner.store.remove(&self.user).await?;
+                Err(AppError::Unauthorized)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    pub async fn request_json(self) -> crate::Result<Value> {
+        let res = self.request().await?;
+        Ok(res.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_string_contains, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::keys::ConsumerKey;
+
+    fn test_token() -> AccessToken {
+        AccessToken {
+            token: "token".into(),
+            secret: String::from("secret").into(),
+        }
+    }
+
+    fn test_sessions(server: &MockServer) -> UserSessions<u32, MemoryTokenStore<u32>> {
+        let client = Client::new(Url::parse(&server.uri()).unwrap()).authorized_from_key(
+            ConsumerKey::new("key".into(), String::from("secret").into(), None),
+        );
+        UserSessions::new(client, MemoryTokenStore::new())
+    }
+
+    #[test]
+    fn rate_limit_allows_bursts_and_spaces_requests() {
+        let limiter = Limiter::new(RateLimit::new(2, Duration::from_secs(1)));
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve(&1, now), Duration::ZERO);
+        assert_eq!(limiter.reserve(&1, now), Duration::ZERO);
+        assert_eq!(limiter.reserve(&1, now), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(&1, now), Duration::from_secs(1));
+        // other users are not affected
+        assert_eq!(limiter.reserve(&2, now), Duration::ZERO);
+        // the limit recovers over time
+        assert_eq!(
+            limiter.reserve(&2, now + Duration::from_secs(5)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_user_has_no_builder() {
+        let server = MockServer::start().await;
+        let sessions = test_sessions(&server);
+
+        assert!(sessions.builder(&1, "users/user").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_evicted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let sessions = test_sessions(&server);
+        sessions.insert_token(1, test_token()).await.unwrap();
+        sessions.insert_token(2, test_token()).await.unwrap();
+
+        let builder = sessions.builder(&1, "users/user").await.unwrap().unwrap();
+        let res = tokio::spawn(builder.request()).await.unwrap();
+
+        assert!(res.is_err());
+        assert!(sessions.store().get(&1).await.unwrap().is_none());
+        assert!(sessions.store().get(&2).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn recovered_token_is_stored() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("oauth_token=granted"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+                "message": "Your access token lacks some of the required scopes.",
+                "error": "method_forbidden",
+                "reason": "scope_missing",
+                "missing_scopes": ["grades"],
+            })))
+            .mount(&server)
+            .await;
+        let client = Client::new(Url::parse(&server.uri()).unwrap())
+            .authorized_from_key(ConsumerKey::new(
+                "key".into(),
+                String::from("secret").into(),
+                None,
+            ))
+            .with_scope_recovery(|_token, _missing| async {
+                Some(AccessToken {
+                    token: "granted".into(),
+                    secret: String::from("secret").into(),
+                })
+            });
+        let sessions = UserSessions::new(client, MemoryTokenStore::new());
+        sessions.insert_token(1, test_token()).await.unwrap();
+
+        let builder = sessions
+            .builder(&1, "grades/terms2")
+            .await
+            .unwrap()
+            .unwrap();
+        builder.request().await.unwrap();
+
+        assert_eq!(
+            sessions.store().get(&1).await.unwrap().unwrap().token,
+            "granted"
+        );
+    }
+
+    #[tokio::test]
+    async fn valid_token_is_kept() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "1" })))
+            .mount(&server)
+            .await;
+        let sessions = test_sessions(&server);
+        sessions.insert_token(1, test_token()).await.unwrap();
+
+        let builder = sessions.builder(&1, "users/user").await.unwrap().unwrap();
+        let res = builder.request_json().await.unwrap();
+
+        assert_eq!(res, json!({ "id": "1" }));
+        assert!(sessions.store().get(&1).await.unwrap().is_some());
+    }
+}
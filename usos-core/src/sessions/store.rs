@@ -0,0 +1,214 @@
+//! Storage of user access tokens.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{io::AsyncWriteExt, sync::RwLock};
+
+use crate::api::auth::AccessToken;
+
+/// Storage that maps application user identifiers to their USOS API access tokens.
+///
+/// Implement this trait to keep the tokens in a database, a cache or any other persistent storage.
+/// [`MemoryTokenStore`] is provided for applications that do not need persistence, and [`FileTokenStore`] for ones
+/// that keep the tokens in a local file.
+#[async_trait]
+pub trait TokenStore<K>: Send + Sync {
+    /// Returns the access token of the user, if there is one.
+    async fn get(&self, user: &K) -> crate::Result<Option<AccessToken>>;
+
+    /// Saves the access token of the user, replacing the previous one.
+    async fn insert(&self, user: K, token: AccessToken) -> crate::Result<()>;
+
+    /// Removes the access token of the user, returning it if it was present.
+    async fn remove(&self, user: &K) -> crate::Result<Option<AccessToken>>;
+}
+
+/// In-memory [`TokenStore`]. The tokens are lost when the store is dropped.
+#[derive(Debug)]
+pub struct MemoryTokenStore<K> {
+    tokens: RwLock<HashMap<K, AccessToken>>,
+}
+
+impl<K> MemoryTokenStore<K> {
+    pub fn new() -> Self {
+        Self {
+            tokens: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K> Default for MemoryTokenStore<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl<K> TokenStore<K> for MemoryTokenStore<K>
+where
+    K: Eq + Hash + Send + Sync,
+{
+    async fn get(&self, user: &K) -> crate::Result<Option<AccessToken>> {
+        Ok(self.tokens.read().await.get(user).cloned())
+    }
+
+    async fn insert(&self, user: K, token: AccessToken) -> crate::Result<()> {
+        self.tokens.write().await.insert(user, token);
+        Ok(())
+    }
+
+    async fn remove(&self, user: &K) -> crate::Result<Option<AccessToken>> {
+        Ok(self.tokens.write().await.remove(user))
+    }
+}
+
+/// [`TokenStore`] persisted in a JSON file, e.g. the `token_store` of a [configuration profile](crate::config::Profile).
+///
+/// The tokens are loaded when the store is opened and the whole file is rewritten on every change,
+/// so it is meant for a moderate number of users.
+#[derive(Debug)]
+pub struct FileTokenStore<K> {
+    path: PathBuf,
+    tokens: RwLock<HashMap<K, AccessToken>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken<K> {
+    user: K,
+    token: String,
+    secret: String,
+}
+
+impl<K> FileTokenStore<K>
+where
+    K: Serialize + DeserializeOwned + Eq + Hash,
+{
+    /// Opens the store, loading the tokens from the file. A missing file is treated as an empty store and is created
+    /// on the first change.
+    pub async fn open(path: impl Into<PathBuf>) -> crate::Result<Self> {
+        let path = path.into();
+        let tokens = match tokio::fs::read(&path).await {
+            Ok(content) => serde_json::from_slice::<Vec<StoredToken<K>>>(&content)
+                .with_context(|| format!("Invalid token store {}", path.display()))?
+                .into_iter()
+                .map(|stored| {
+                    let token = AccessToken {
+                        token: stored.token,
+                        secret: SecretString::new(stored.secret),
+                    };
+                    (stored.user, token)
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(anyhow::Error::new(e)
+                    .context(format!("Failed to read token store {}", path.display()))
+                    .into())
+            }
+        };
+
+        Ok(Self {
+            path,
+            tokens: RwLock::new(tokens),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the tokens to a temporary file readable only by the owner and moves it over the store,
+    /// so that the store is never left half-written.
+    async fn save(&self, tokens: &HashMap<K, AccessToken>) -> crate::Result<()> {
+        let stored = tokens
+            .iter()
+            .map(|(user, token)| StoredToken {
+                user,
+                token: token.token.clone(),
+                secret: token.secret.expose_secret().clone(),
+            })
+            .collect::<Vec<_>>();
+        let content = serde_json::to_vec_pretty(&stored)?;
+
+        let temporary = self.path.with_extension("tmp");
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let write = async {
+            let mut file = options.open(&temporary).await?;
+            file.write_all(&content).await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&temporary, &self.path).await
+        };
+        Ok(write
+            .await
+            .with_context(|| format!("Failed to write token store {}", self.path.display()))?)
+    }
+}
+
+#[async_trait]
+impl<K> TokenStore<K> for FileTokenStore<K>
+where
+    K: Serialize + DeserializeOwned + Eq + Hash + Send + Sync,
+{
+    async fn get(&self, user: &K) -> crate::Result<Option<AccessToken>> {
+        Ok(self.tokens.read().await.get(user).cloned())
+    }
+
+    async fn insert(&self, user: K, token: AccessToken) -> crate::Result<()> {
+        let mut tokens = self.tokens.write().await;
+        tokens.insert(user, token);
+        self.save(&tokens).await
+    }
+
+    async fn remove(&self, user: &K) -> crate::Result<Option<AccessToken>> {
+        let mut tokens = self.tokens.write().await;
+        let removed = tokens.remove(user);
+        if removed.is_some() {
+            self.save(&tokens).await?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(token: &str) -> AccessToken {
+        AccessToken {
+            token: token.to_string(),
+            secret: SecretString::new(format!("{token} secret")),
+        }
+    }
+
+    #[tokio::test]
+    async fn file_store_persists_tokens() {
+        let path =
+            std::env::temp_dir().join(format!("usos_test_tokens_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = FileTokenStore::<u32>::open(&path).await.unwrap();
+        assert!(store.get(&1).await.unwrap().is_none());
+        store.insert(1, token("first")).await.unwrap();
+        store.insert(2, token("second")).await.unwrap();
+        store.remove(&2).await.unwrap();
+
+        let reopened = FileTokenStore::<u32>::open(&path).await.unwrap();
+        let first = reopened.get(&1).await.unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(first.token, "first");
+        assert_eq!(first.secret.expose_secret(), "first secret");
+        assert!(reopened.get(&2).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,9 @@
+use usos_core::api::selector::UsosFields;
+
+#[derive(UsosFields)]
+enum Visibility {
+    Public,
+    All,
+}
+
+fn main() {}
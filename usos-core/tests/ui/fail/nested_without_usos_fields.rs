@@ -0,0 +1,13 @@
+use usos_core::api::selector::UsosFields;
+
+struct Name {
+    pl: String,
+}
+
+#[derive(UsosFields)]
+struct User {
+    #[usos(nested)]
+    name: Name,
+}
+
+fn main() {}
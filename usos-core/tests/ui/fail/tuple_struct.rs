@@ -0,0 +1,6 @@
+use usos_core::api::selector::UsosFields;
+
+#[derive(UsosFields)]
+struct FacultyId(String);
+
+fn main() {}
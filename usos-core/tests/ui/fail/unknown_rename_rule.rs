@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use usos_core::api::selector::UsosFields;
+
+#[derive(Deserialize, UsosFields)]
+#[serde(rename_all = "Title Case")]
+struct User {
+    user_id: String,
+}
+
+fn main() {}
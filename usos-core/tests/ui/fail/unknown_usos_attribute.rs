@@ -0,0 +1,9 @@
+use usos_core::api::selector::UsosFields;
+
+#[derive(UsosFields)]
+struct User {
+    #[usos(flatten)]
+    id: String,
+}
+
+fn main() {}
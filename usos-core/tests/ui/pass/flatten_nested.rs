@@ -0,0 +1,32 @@
+use serde::Deserialize;
+use usos_core::api::selector::UsosFields;
+
+#[derive(Deserialize, UsosFields)]
+struct Name {
+    pl: Option<String>,
+    en: Option<String>,
+}
+
+#[derive(Deserialize, UsosFields)]
+struct Common {
+    id: String,
+    #[usos(nested)]
+    name: Name,
+}
+
+#[derive(Deserialize, UsosFields)]
+struct Faculty {
+    #[serde(flatten)]
+    common: Common,
+    #[usos(nested)]
+    path: Vec<Common>,
+    #[usos(nested)]
+    parent: Option<Box<Common>>,
+}
+
+fn main() {
+    assert_eq!(
+        Faculty::selector().to_string(),
+        "id|name[pl|en]|path[id|name[pl|en]]|parent[id|name[pl|en]]"
+    );
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use usos_core::api::selector::UsosFields;
+
+#[derive(Deserialize, UsosFields)]
+struct Course {
+    id: String,
+    #[serde(rename = "name")]
+    course_name: String,
+    #[serde(rename(serialize = "ects", deserialize = "ects_credits_simplified"))]
+    ects: f32,
+    r#type: String,
+}
+
+fn main() {
+    assert_eq!(
+        Course::selector().to_string(),
+        "id|name|ects_credits_simplified|type"
+    );
+}
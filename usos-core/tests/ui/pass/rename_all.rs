@@ -0,0 +1,29 @@
+use serde::Deserialize;
+use usos_core::api::selector::UsosFields;
+
+#[derive(Deserialize, UsosFields)]
+#[serde(rename_all = "camelCase")]
+struct Camel {
+    user_id: String,
+    #[serde(rename = "first_name")]
+    first_name: String,
+    is_staff: bool,
+}
+
+#[derive(Deserialize, UsosFields)]
+#[serde(rename_all(serialize = "snake_case", deserialize = "SCREAMING-KEBAB-CASE"))]
+struct Kebab {
+    user_id: String,
+}
+
+#[derive(Deserialize, UsosFields)]
+#[serde(deny_unknown_fields, rename_all = "PascalCase")]
+struct Pascal {
+    user_id: String,
+}
+
+fn main() {
+    assert_eq!(Camel::selector().to_string(), "userId|first_name|isStaff");
+    assert_eq!(Kebab::selector().to_string(), "USER-ID");
+    assert_eq!(Pascal::selector().to_string(), "UserId");
+}
@@ -0,0 +1,17 @@
+use serde::Deserialize;
+use usos_core::api::selector::UsosFields;
+
+#[derive(Deserialize, UsosFields)]
+struct User {
+    id: String,
+    #[serde(skip)]
+    cached: bool,
+    #[serde(skip_deserializing)]
+    fetched_at: Option<u64>,
+    #[serde(default, skip_serializing)]
+    email: Option<String>,
+}
+
+fn main() {
+    assert_eq!(User::selector().to_string(), "id|email");
+}
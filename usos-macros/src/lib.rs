@@ -0,0 +1,247 @@
+//! Procedural macros for the `usos-core` crate.
+//!
+//! Use the macros through their re-exports in `usos-core`, e.g. `usos_core::api::selector::UsosFields`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument, LitStr,
+    PathArguments, Token, Type,
+};
+
+/// Derives `usos_core::api::selector::UsosFields`, producing the USOS API `fields` selector of a struct.
+///
+/// Every named field is selected under its name, or under the name given with `#[serde(rename = "...")]`.
+/// Names of fields without `rename` follow the container's `#[serde(rename_all = "...")]`, if any.
+/// Fields with `#[serde(skip)]` or `#[serde(skip_deserializing)]` are left out.
+///
+/// Fields marked with `#[usos(nested)]` must have a type implementing `UsosFields` (possibly wrapped in `Option` or `Vec`),
+/// and are selected together with their subfields, e.g. `faculty[id|name]`.
+/// Fields marked with `#[serde(flatten)]` must also implement `UsosFields`, and their fields are selected at the same level.
+#[proc_macro_derive(UsosFields, attributes(usos))]
+pub fn derive_usos_fields(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.span(),
+            "UsosFields can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(
+            input.span(),
+            "UsosFields can only be derived for structs with named fields",
+        ));
+    };
+
+    let rename_all = RenameRule::parse(&input.attrs)?;
+    let mut selector = quote! { ::usos_core::api::selector::Selector::new() };
+    for field in &fields.named {
+        let attributes = FieldAttributes::parse(field)?;
+        if attributes.skip {
+            continue;
+        }
+
+        let name = attributes.rename.unwrap_or_else(|| {
+            let ident = field.ident.as_ref().expect("named fields have identifiers");
+            rename_all.apply(ident.to_string().trim_start_matches("r#"))
+        });
+        let inner = inner_type(&field.ty);
+        selector = if attributes.flatten {
+            quote! { #selector.merge(<#inner as ::usos_core::api::selector::UsosFields>::selector()) }
+        } else if attributes.nested {
+            quote! { #selector.nested(#name, <#inner as ::usos_core::api::selector::UsosFields>::selector()) }
+        } else {
+            quote! { #selector.field(#name) }
+        };
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::usos_core::api::selector::UsosFields for #ident #type_generics #where_clause {
+            fn selector() -> ::usos_core::api::selector::Selector {
+                #selector
+            }
+        }
+    })
+}
+
+/// Case convention of `#[serde(rename_all = "...")]`, applied to snake_case field names.
+#[derive(Clone, Copy, Default)]
+enum RenameRule {
+    #[default]
+    None,
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut rule = Self::None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("rename_all") {
+                    return skip_meta_value(&meta);
+                }
+                if meta.input.peek(Token![=]) {
+                    rule = Self::from_lit(&meta.value()?.parse::<LitStr>()?)?;
+                    return Ok(());
+                }
+                // rename_all(serialize = "...", deserialize = "...")
+                meta.parse_nested_meta(|nested| {
+                    if nested.path.is_ident("deserialize") {
+                        rule = Self::from_lit(&nested.value()?.parse::<LitStr>()?)?;
+                        Ok(())
+                    } else {
+                        skip_meta_value(&nested)
+                    }
+                })
+            })?;
+        }
+        Ok(rule)
+    }
+
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        Ok(match lit.value().as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            other => {
+                return Err(Error::new(
+                    lit.span(),
+                    format!("unknown rename rule `rename_all = {other:?}`"),
+                ))
+            }
+        })
+    }
+
+    fn apply(self, field: &str) -> String {
+        match self {
+            Self::None | Self::Lower | Self::Snake => field.to_string(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Pascal => field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                        .unwrap_or_default()
+                })
+                .collect(),
+            Self::Camel => {
+                let pascal = Self::Pascal.apply(field);
+                let mut chars = pascal.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            }
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldAttributes {
+    rename: Option<String>,
+    skip: bool,
+    flatten: bool,
+    nested: bool,
+}
+
+impl FieldAttributes {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut attributes = Self::default();
+        for attr in &field.attrs {
+            if attr.path().is_ident("serde") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        if meta.input.peek(Token![=]) {
+                            attributes.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else {
+                            // rename(serialize = "...", deserialize = "...")
+                            meta.parse_nested_meta(|nested| {
+                                if nested.path.is_ident("deserialize") {
+                                    attributes.rename =
+                                        Some(nested.value()?.parse::<LitStr>()?.value());
+                                    Ok(())
+                                } else {
+                                    skip_meta_value(&nested)
+                                }
+                            })?;
+                        }
+                        return Ok(());
+                    } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing")
+                    {
+                        attributes.skip = true;
+                    } else if meta.path.is_ident("flatten") {
+                        attributes.flatten = true;
+                    }
+                    // other serde attributes do not affect the selector
+                    skip_meta_value(&meta)
+                })?;
+            } else if attr.path().is_ident("usos") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("nested") {
+                        attributes.nested = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported usos attribute, expected `nested`"))
+                    }
+                })?;
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+/// Consumes the rest of a serde attribute, e.g. `= "..."` or `(...)`.
+fn skip_meta_value(meta: &syn::meta::ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta_value(&nested))?;
+    }
+    Ok(())
+}
+
+/// Unwraps `Option<T>`, `Vec<T>` and `Box<T>` (possibly nested) to `T`.
+fn inner_type(ty: &Type) -> &Type {
+    let Type::Path(path) = ty else {
+        return ty;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return ty;
+    };
+    if !["Option", "Vec", "Box"].contains(&segment.ident.to_string().as_str()) {
+        return ty;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(GenericArgument::Type(inner)) => inner_type(inner),
+            _ => ty,
+        },
+        _ => ty,
+    }
+}
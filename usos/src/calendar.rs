@@ -1,11 +1,38 @@
-use serde::Deserialize;
-use time::PrimitiveDateTime;
+//! Academic calendar: holidays, breaks, exam sessions and days off.
 
-use usos_core::api::types::language::LanguageDictionary;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use time::{Date, Weekday};
+use usos_core::{
+    api::{
+        auth::AccessToken,
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+        selector::UsosFields,
+        types::{
+            ids::FacultyId,
+            language::LanguageDictionary,
+            time::{UsosDate, UsosDateTime},
+        },
+    },
+    client::Client,
+};
 
-#[derive(Debug, Deserialize)]
-enum EventType {
+use crate::faculties::faculty::FacultyReference;
+
+/// Type of a calendar event.
+///
+/// Types not known to this crate are represented by [`EventType::Unknown`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum EventType {
+    /// Rector's day off
     Rector,
+    /// Dean's day off
     Dean,
     Holidays,
     PublicHolidays,
@@ -13,19 +40,416 @@ enum EventType {
     Break,
     LinksEdit,
     Undefined,
+    /// Type not known to this crate, with its code.
+    Unknown(String),
+}
+
+impl EventType {
+    /// Whether classes are not held during events of this type.
+    pub fn is_day_off(&self) -> bool {
+        matches!(
+            self,
+            EventType::Rector
+                | EventType::Dean
+                | EventType::Holidays
+                | EventType::PublicHolidays
+                | EventType::ExamSession
+                | EventType::Break
+        )
+    }
+
+    /// Whether the event is a holiday or a break.
+    pub fn is_holiday(&self) -> bool {
+        matches!(
+            self,
+            EventType::Holidays | EventType::PublicHolidays | EventType::Break
+        )
+    }
+}
+
+impl Display for EventType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EventType::Rector => write!(f, "rector"),
+            EventType::Dean => write!(f, "dean"),
+            EventType::Holidays => write!(f, "holidays"),
+            EventType::PublicHolidays => write!(f, "public_holidays"),
+            EventType::ExamSession => write!(f, "exam_session"),
+            EventType::Break => write!(f, "break"),
+            EventType::LinksEdit => write!(f, "links_edit"),
+            EventType::Undefined => write!(f, "undefined"),
+            EventType::Unknown(code) => write!(f, "{code}"),
+        }
+    }
+}
+
+impl From<&str> for EventType {
+    fn from(s: &str) -> Self {
+        match s {
+            "rector" => Self::Rector,
+            "dean" => Self::Dean,
+            "holidays" => Self::Holidays,
+            "public_holidays" => Self::PublicHolidays,
+            "exam_session" => Self::ExamSession,
+            "break" => Self::Break,
+            "links_edit" => Self::LinksEdit,
+            "undefined" => Self::Undefined,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct CalendarEvent {
-    #[serde(rename = "id")]
-    id: String,
-    #[serde(rename = "name")]
-    name: LanguageDictionary,
-    // #[serde(rename = "start_date", with = "usos_core::datetime_string")] // FIXME
-    start_date: PrimitiveDateTime,
-    // #[serde(rename = "end_date", with = "usos_core::datetime_string")] // FIXME
-    end_date: PrimitiveDateTime,
-    // faculty: Faculty
+impl From<String> for EventType {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<EventType> for String {
+    fn from(event_type: EventType) -> Self {
+        event_type.to_string()
+    }
+}
+
+impl FromStr for EventType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, UsosFields)]
+pub struct CalendarEvent {
+    pub id: String,
+    pub name: LanguageDictionary,
+    pub start_date: UsosDateTime,
+    pub end_date: UsosDateTime,
+    /// [`None`] for events of the whole institution
+    #[usos(nested)]
+    pub faculty: Option<FacultyReference>,
     #[serde(rename = "type")]
-    event_type: EventType,
+    pub event_type: EventType,
+}
+
+impl CalendarEvent {
+    /// Whether the event lasts during any part of the day. Both the start and the end day are included.
+    pub fn covers(&self, date: Date) -> bool {
+        self.start_date.0.date() <= date && date <= self.end_date.0.date()
+    }
+
+    /// Whether the event lasts during any part of the range. Both ends of the range are included.
+    pub fn overlaps(&self, start: Date, end: Date) -> bool {
+        self.start_date.0.date() <= end && start <= self.end_date.0.date()
+    }
+}
+
+#[derive(Serialize)]
+struct SearchCalendar<'a> {
+    faculty_id: &'a FacultyId,
+    start_date: Option<UsosDate>,
+    end_date: Option<UsosDate>,
+}
+
+impl Endpoint for SearchCalendar<'_> {
+    const PATH: &'static str = "calendar/search";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = Vec<CalendarEvent>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        let mut params = Params::from_serialize(self)?;
+        params.insert(String::from("fields"), CalendarEvent::selector().into());
+        Ok(params)
+    }
+}
+
+#[derive(Serialize)]
+struct UserEvents {
+    start_date: Option<UsosDate>,
+    end_date: Option<UsosDate>,
+}
+
+impl Endpoint for UserEvents {
+    const PATH: &'static str = "calendar/user_events";
+    const CONSUMER: Requirement = Requirement::Required;
+    type Token = token::Required;
+    type Response = Vec<CalendarEvent>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        let mut params = Params::from_serialize(self)?;
+        params.insert(String::from("fields"), CalendarEvent::selector().into());
+        Ok(params)
+    }
+}
+
+/// calendar/search
+///
+/// Consumer: ignored
+///
+/// Token: ignored
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+///
+/// Returns the events of the faculty (including the ones of the whole institution) within the date range.
+pub async fn search_calendar(
+    client: &Client,
+    faculty_id: &FacultyId,
+    start_date: Option<UsosDate>,
+    end_date: Option<UsosDate>,
+) -> usos_core::Result<Vec<CalendarEvent>> {
+    client
+        .call(&SearchCalendar {
+            faculty_id,
+            start_date,
+            end_date,
+        })
+        .await
+}
+
+/// calendar/user_events
+///
+/// Consumer: required
+///
+/// Token: required
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+///
+/// Returns the events of the faculties the user is related to, within the date range.
+pub async fn get_user_events(
+    client: &Client,
+    token: &AccessToken,
+    start_date: Option<UsosDate>,
+    end_date: Option<UsosDate>,
+) -> usos_core::Result<Vec<CalendarEvent>> {
+    client
+        .call_with_token(
+            &UserEvents {
+                start_date,
+                end_date,
+            },
+            token,
+        )
+        .await
+}
+
+/// Calendar events with helpers answering the common questions about the academic year.
+#[derive(Debug, Clone, Default)]
+pub struct AcademicCalendar {
+    events: Vec<CalendarEvent>,
+}
+
+impl FromIterator<CalendarEvent> for AcademicCalendar {
+    fn from_iter<T: IntoIterator<Item = CalendarEvent>>(iter: T) -> Self {
+        let mut events = Vec::from_iter(iter);
+        events.sort_by_key(|event| (event.start_date, event.end_date));
+        Self { events }
+    }
+}
+
+impl AcademicCalendar {
+    /// Events sorted by their start.
+    pub fn events(&self) -> &[CalendarEvent] {
+        &self.events
+    }
+
+    /// Whether classes are held on the day: it is a weekday without any [day off](EventType::is_day_off).
+    pub fn is_teaching_day(&self, date: Date) -> bool {
+        !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday)
+            && !self
+                .events
+                .iter()
+                .any(|event| event.event_type.is_day_off() && event.covers(date))
+    }
+
+    /// Holidays and breaks that last during any part of the range, e.g. of a term (see `terms/term`).
+    pub fn holidays_between(&self, start: Date, end: Date) -> Vec<&CalendarEvent> {
+        self.events
+            .iter()
+            .filter(|event| event.event_type.is_holiday() && event.overlaps(start, end))
+            .collect()
+    }
+
+    /// Returns the start of the first exam session that has not ended before the date.
+    ///
+    /// The start may be earlier than the date if the session is in progress.
+    pub fn exam_session_start(&self, date: Date) -> Option<UsosDateTime> {
+        self.events
+            .iter()
+            .find(|event| {
+                event.event_type == EventType::ExamSession && event.end_date.0.date() >= date
+            })
+            .map(|event| event.start_date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use time::macros::{date, datetime};
+    use usos_core::errors::AppError;
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_authorized_client, mock_client, usos_method};
+
+    fn event(id: &str, event_type: &str, start: &str, end: &str) -> Value {
+        json!({
+            "id": id,
+            "name": { "pl": id, "en": id },
+            "start_date": format!("{start} 00:00:00"),
+            "end_date": format!("{end} 00:00:00"),
+            "faculty": null,
+            "type": event_type,
+        })
+    }
+
+    fn events() -> Value {
+        json!([
+            event("exams-winter", "exam_session", "2025-01-27", "2025-02-09"),
+            event("christmas", "holidays", "2024-12-23", "2025-01-06"),
+            event(
+                "independence-day",
+                "public_holidays",
+                "2024-11-11",
+                "2024-11-11"
+            ),
+            event("rectors-day", "rector", "2024-11-12", "2024-11-12"),
+            event("links", "links_edit", "2024-09-01", "2024-09-30"),
+            event("exams-summer", "exam_session", "2025-06-16", "2025-06-29"),
+        ])
+    }
+
+    fn calendar() -> AcademicCalendar {
+        serde_json::from_value::<Vec<CalendarEvent>>(events())
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn events_are_decoded() {
+        let event = CalendarEvent::deserialize(json!({
+            "id": "1234",
+            "name": { "pl": "Dzień rektorski", "en": "Rector's day" },
+            "start_date": "2024-11-12 00:00:00",
+            "end_date": "2024-11-12 23:59:59",
+            "faculty": { "id": "W4N", "name": { "pl": "Wydział Informatyki" } },
+            "type": "rector",
+        }))
+        .unwrap();
+
+        assert_eq!(event.start_date.0, datetime!(2024-11-12 00:00:00));
+        assert_eq!(event.end_date.0, datetime!(2024-11-12 23:59:59));
+        assert_eq!(event.event_type, EventType::Rector);
+        assert_eq!(event.faculty.unwrap().id, FacultyId::new("W4N"));
+    }
+
+    #[test]
+    fn unknown_event_types_are_kept() {
+        let event_type = EventType::deserialize(json!("juwenalia")).unwrap();
+
+        assert_eq!(event_type, EventType::Unknown(String::from("juwenalia")));
+        assert_eq!(event_type.to_string(), "juwenalia");
+        assert!(!event_type.is_day_off());
+    }
+
+    #[test]
+    fn teaching_days_skip_weekends_and_days_off() {
+        let calendar = calendar();
+
+        assert!(calendar.is_teaching_day(date!(2024 - 11 - 13)));
+        assert!(!calendar.is_teaching_day(date!(2024 - 11 - 11)));
+        assert!(!calendar.is_teaching_day(date!(2024 - 11 - 12)));
+        assert!(!calendar.is_teaching_day(date!(2024 - 11 - 16)));
+        assert!(!calendar.is_teaching_day(date!(2025 - 01 - 02)));
+        assert!(!calendar.is_teaching_day(date!(2025 - 01 - 28)));
+    }
+
+    #[test]
+    fn holidays_within_term_are_listed() {
+        let calendar = calendar();
+        let ids = calendar
+            .holidays_between(date!(2024 - 10 - 01), date!(2025 - 02 - 23))
+            .into_iter()
+            .map(|event| event.id.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, ["independence-day", "christmas"]);
+        assert!(calendar
+            .holidays_between(date!(2025 - 03 - 01), date!(2025 - 06 - 30))
+            .is_empty());
+    }
+
+    #[test]
+    fn next_exam_session_is_found() {
+        let calendar = calendar();
+
+        assert_eq!(
+            calendar.exam_session_start(date!(2024 - 10 - 01)),
+            Some(UsosDateTime(datetime!(2025-01-27 00:00:00)))
+        );
+        assert_eq!(
+            calendar.exam_session_start(date!(2025 - 02 - 01)),
+            Some(UsosDateTime(datetime!(2025-01-27 00:00:00)))
+        );
+        assert_eq!(
+            calendar.exam_session_start(date!(2025 - 02 - 10)),
+            Some(UsosDateTime(datetime!(2025-06-16 00:00:00)))
+        );
+        assert_eq!(calendar.exam_session_start(date!(2025 - 07 - 01)), None);
+    }
+
+    #[tokio::test]
+    async fn faculty_calendar_is_searched() {
+        let server = MockServer::start().await;
+        usos_method("calendar/search")
+            .and(body_string_contains("end_date=2025-02-23"))
+            .and(body_string_contains("faculty_id=W4N"))
+            .and(body_string_contains("start_date=2024-10-01"))
+            .respond_with(json_response(events()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let events = search_calendar(
+            &mock_client(&server),
+            &FacultyId::new("W4N"),
+            Some(UsosDate(date!(2024 - 10 - 01))),
+            Some(UsosDate(date!(2025 - 02 - 23))),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(events.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn user_events_are_fetched_with_token() {
+        let server = MockServer::start().await;
+        usos_method("calendar/user_events")
+            .and(body_string_contains("oauth_token=token"))
+            .respond_with(json_response(events()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let token = AccessToken {
+            token: String::from("token"),
+            secret: String::from("secret").into(),
+        };
+
+        let events = get_user_events(&mock_authorized_client(&server), &token, None, None)
+            .await
+            .unwrap();
+        let error = get_user_events(&mock_client(&server), &token, None, None)
+            .await
+            .unwrap_err();
+
+        assert_eq!(events.len(), 6);
+        assert!(matches!(error, AppError::InvalidRequest(_)));
+    }
 }
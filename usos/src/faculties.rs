@@ -1,2 +1,3 @@
 pub mod faculty;
 pub mod search;
+pub mod tree;
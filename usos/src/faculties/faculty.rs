@@ -1,8 +1,68 @@
 use serde::{Deserialize, Deserializer};
-use serde_json::Value;
-use std::{collections::HashMap, ops::Deref};
-use usos_core::api::types::language::LanguageDictionary;
-use usos_core::client::CLIENT;
+use std::collections::HashMap;
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::{ParamString, Params},
+        selector::UsosFields,
+        types::{ids::FacultyId, language::LanguageDictionary},
+    },
+    client::Client,
+};
+
+use super::search::Visibility;
+
+struct GetFaculty<'a>(&'a FacultyId);
+
+impl Endpoint for GetFaculty<'_> {
+    const PATH: &'static str = "fac/faculty";
+    const CONSUMER: Requirement = Requirement::Optional;
+    type Token = token::Optional;
+    type Response = Faculty;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from([
+            ("fac_id", ParamString::from(self.0)),
+            ("fields", Faculty::selector().into()),
+        ]))
+    }
+}
+
+struct GetFaculties<'a>(&'a [FacultyId]);
+
+impl Endpoint for GetFaculties<'_> {
+    const PATH: &'static str = "fac/faculties";
+    const CONSUMER: Requirement = Requirement::Optional;
+    type Token = token::Optional;
+    type Response = HashMap<FacultyId, Option<Faculty>>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from([
+            ("fac_ids", ParamString::from(self.0)),
+            ("fields", Faculty::selector().into()),
+        ]))
+    }
+}
+
+struct GetSubfaculties<'a> {
+    faculty_id: &'a FacultyId,
+    visibility: Visibility,
+}
+
+impl Endpoint for GetSubfaculties<'_> {
+    const PATH: &'static str = "fac/subfaculties";
+    const CONSUMER: Requirement = Requirement::Optional;
+    type Token = token::Optional;
+    type Response = Vec<FacultyReference>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from([
+            ("fac_id", ParamString::from(self.faculty_id)),
+            ("fields", FacultyReference::selector().into()),
+            ("visibility", self.visibility.to_string().into()),
+        ]))
+    }
+}
 
 /// fac/faculty
 ///
@@ -13,39 +73,96 @@ use usos_core::client::CLIENT;
 /// Scopes: n/a
 ///
 /// SSL: not required
-pub async fn get_faculty(faculty_id: &str) {}
+pub async fn get_faculty(client: &Client, faculty_id: &FacultyId) -> usos_core::Result<Faculty> {
+    client.call(&GetFaculty(faculty_id)).await
+}
 
-#[derive(Debug, Deserialize)]
+/// fac/faculties
+///
+/// Consumer: optional
+///
+/// Token: optional
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+///
+/// Faculties that do not exist are mapped to [`None`].
+pub async fn get_faculties(
+    client: &Client,
+    faculty_ids: &[FacultyId],
+) -> usos_core::Result<HashMap<FacultyId, Option<Faculty>>> {
+    if faculty_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    client.call(&GetFaculties(faculty_ids)).await
+}
+
+/// fac/subfaculties
+///
+/// Consumer: optional
+///
+/// Token: optional
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_subfaculties(
+    client: &Client,
+    faculty_id: &FacultyId,
+    visibility: Visibility,
+) -> usos_core::Result<Vec<FacultyReference>> {
+    client
+        .call(&GetSubfaculties {
+            faculty_id,
+            visibility,
+        })
+        .await
+}
+
+#[derive(Debug, Deserialize, UsosFields)]
 pub struct Faculty {
-    id: String,
-    name: LanguageDictionary,
-    profile_url: String,
-    homepage_url: Option<String>,
-    phone_numbers: Vec<String>,
-    phone_numbers2: Vec<PhoneNumber>,
-    postal_address: String,
-    email: Option<String>,
-    is_public: bool,
-    // stats: FacultyStats,
-    // path: Vec<Faculty>,
-    static_map_urls: StaticMapUrls,
+    pub id: FacultyId,
+    pub name: LanguageDictionary,
+    pub profile_url: String,
+    pub homepage_url: Option<String>,
+    pub phone_numbers: Vec<String>,
+    pub phone_numbers2: Vec<PhoneNumber>,
+    pub postal_address: String,
+    pub email: Option<String>,
+    pub is_public: bool,
+    #[usos(nested)]
+    pub stats: FacultyStats,
+    /// Ancestors of the faculty, starting from the root of the hierarchy
+    #[usos(nested)]
+    pub path: Vec<FacultyReference>,
+    pub static_map_urls: StaticMapUrls,
+}
+
+/// Identifier and name of a faculty, e.g. an element of [`Faculty::path`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, UsosFields)]
+pub struct FacultyReference {
+    pub id: FacultyId,
+    pub name: LanguageDictionary,
 }
 
 #[derive(Debug, Deserialize)]
-struct PhoneNumber {
-    comment: Option<String>,
-    number: String,
+pub struct PhoneNumber {
+    pub comment: Option<String>,
+    pub number: String,
     #[serde(rename = "type")]
-    phone_type: String,
+    pub phone_type: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct FacultyStats {
-    course_count: Option<u32>,
-    programme_count: Option<u32>,
-    staff_count: Option<u32>,
-    subfaculty_count: Option<u32>,
-    public_subfaculty_count: Option<u32>,
+/// Counts are [`None`] if the installation does not share them.
+#[derive(Debug, Deserialize, UsosFields)]
+pub struct FacultyStats {
+    pub course_count: Option<u32>,
+    pub programme_count: Option<u32>,
+    pub staff_count: Option<u32>,
+    pub subfaculty_count: Option<u32>,
+    pub public_subfaculty_count: Option<u32>,
 }
 
 /// Square: 100x100, 200x200, 300x300
@@ -54,7 +171,7 @@ struct FacultyStats {
 ///
 /// Landscape: 1000x250
 #[derive(Debug, Hash, PartialEq, Eq)]
-enum Resolution {
+pub enum Resolution {
     /// [`Quality::Low`] 100px x 100px
     ///
     /// [`Quality::Medium`] 200px x 200px
@@ -69,6 +186,8 @@ enum Resolution {
     Wide(Quality),
     /// 1000px x 250px
     Landscape,
+    /// Resolution not known to this crate, e.g. `"1200x600"`.
+    Unknown(String),
 }
 
 #[derive(Debug, Deserialize, Hash, PartialEq, Eq)]
@@ -78,17 +197,22 @@ pub enum Quality {
     High,
 }
 
-#[derive(Debug)]
-struct StaticMapUrls(HashMap<Resolution, String>);
+/// URLs of the maps showing the location of the faculty, by resolution.
+#[derive(Debug, Default)]
+pub struct StaticMapUrls(pub HashMap<Resolution, String>);
 
 impl<'de> Deserialize<'de> for StaticMapUrls {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let map = HashMap::<String, String>::deserialize(deserializer)?;
+        // faculties without a location have no maps, sent as `null` (or with `null` URLs)
+        let map = Option::<HashMap<String, Option<String>>>::deserialize(deserializer)?;
         let mut out = HashMap::new();
-        for (key, value) in map.iter() {
+        for (key, value) in map.unwrap_or_default() {
+            let Some(value) = value else {
+                continue;
+            };
             let resolution = match key.as_str() {
                 "100x100" => Resolution::Square(Quality::Low),
                 "200x200" => Resolution::Square(Quality::Medium),
@@ -97,10 +221,122 @@ impl<'de> Deserialize<'de> for StaticMapUrls {
                 "600x300" => Resolution::Wide(Quality::Medium),
                 "800x400" => Resolution::Wide(Quality::High),
                 "1000x250" => Resolution::Landscape,
-                other => panic!("Invalid resolution {other}"),
+                other => Resolution::Unknown(other.to_string()),
             };
-            out.insert(resolution, value.to_string());
+            out.insert(resolution, value);
         }
         Ok(StaticMapUrls(out))
     }
 }
+
+#[test]
+fn unknown_static_map_resolutions_are_kept() {
+    let urls = StaticMapUrls::deserialize(serde_json::json!({
+        "100x100": "https://example.com/100x100.png",
+        "1200x600": "https://example.com/1200x600.png",
+    }))
+    .unwrap();
+
+    assert_eq!(urls.0.len(), 2);
+    assert!(urls
+        .0
+        .contains_key(&Resolution::Unknown(String::from("1200x600"))));
+}
+
+#[test]
+fn missing_static_maps_are_empty() {
+    let urls = StaticMapUrls::deserialize(serde_json::json!(null)).unwrap();
+    assert!(urls.0.is_empty());
+
+    let urls = StaticMapUrls::deserialize(serde_json::json!({ "100x100": null })).unwrap();
+    assert!(urls.0.is_empty());
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::{json, Value};
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_client, usos_method};
+
+    /// `fac/faculty` response with all fields of [`Faculty`].
+    pub(crate) fn faculty(id: &str, name: &str) -> Value {
+        json!({
+            "id": id,
+            "name": { "pl": name, "en": null },
+            "profile_url": format!("https://usosweb.example.edu.pl/jednostki/{id}"),
+            "homepage_url": null,
+            "phone_numbers": [],
+            "phone_numbers2": [],
+            "postal_address": "Wybrzeże Wyspiańskiego 27, 50-370 Wrocław",
+            "email": null,
+            "is_public": true,
+            "stats": {
+                "course_count": 120,
+                "programme_count": null,
+                "staff_count": 85,
+                "subfaculty_count": 3,
+                "public_subfaculty_count": 2
+            },
+            "path": [{ "id": "00000000", "name": { "pl": "Politechnika Wrocławska" } }],
+            "static_map_urls": null
+        })
+    }
+
+    #[test]
+    fn faculty_selector_selects_nested_fields() {
+        let selector = Faculty::selector().to_string();
+
+        assert!(selector.contains("|stats[course_count|programme_count|staff_count|subfaculty_count|public_subfaculty_count]|"));
+        assert!(selector.contains("|path[id|name]|"));
+    }
+
+    #[tokio::test]
+    async fn faculty_is_fetched() {
+        let server = MockServer::start().await;
+        usos_method("fac/faculty")
+            .and(body_string_contains("fac_id=W4N"))
+            .respond_with(json_response(faculty("W4N", "Wydział Informatyki")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let faculty = get_faculty(&mock_client(&server), &FacultyId::new("W4N"))
+            .await
+            .unwrap();
+
+        assert_eq!(faculty.id, FacultyId::new("W4N"));
+        assert_eq!(faculty.stats.staff_count, Some(85));
+        assert_eq!(faculty.path[0].name.polish(), "Politechnika Wrocławska");
+    }
+
+    #[tokio::test]
+    async fn faculties_are_fetched_in_batch() {
+        let server = MockServer::start().await;
+        usos_method("fac/faculties")
+            .and(body_string_contains("fac_ids=W4N%7CW0"))
+            .respond_with(json_response(json!({
+                "W4N": faculty("W4N", "Wydział Informatyki"),
+                "W0": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let faculties = get_faculties(
+            &mock_client(&server),
+            &[FacultyId::new("W4N"), FacultyId::new("W0")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(faculties.len(), 2);
+        assert!(faculties[&FacultyId::new("W4N")].is_some());
+        assert!(faculties[&FacultyId::new("W0")].is_none());
+        assert!(get_faculties(&mock_client(&server), &[])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
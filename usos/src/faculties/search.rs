@@ -1,9 +1,40 @@
 use serde::Deserialize;
 use std::fmt::{self, Display, Formatter};
-use usos_core::api::types::language::Language;
-use usos_core::client::CLIENT;
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::{ParamString, Params},
+        types::{ids::FacultyId, language::Language},
+    },
+    client::Client,
+};
 
-/// fac/faculty
+struct SearchFaculties<'a> {
+    language: Language,
+    query: &'a str,
+    visibility: Option<Visibility>,
+    num: Option<SearchResults>,
+    start: Option<StartIndex>,
+}
+
+impl Endpoint for SearchFaculties<'_> {
+    const PATH: &'static str = "fac/search";
+    const CONSUMER: Requirement = Requirement::Optional;
+    type Token = token::Optional;
+    type Response = FacultySearch;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from([
+            ("lang", ParamString::from(self.language.clone())),
+            ("query", self.query.into()),
+            ("visibility", self.visibility.map(|v| v.to_string()).into()),
+            ("num", self.num.as_ref().map(|num| num.0).into()),
+            ("start", self.start.as_ref().map(|start| start.0).into()),
+        ]))
+    }
+}
+
+/// fac/search
 ///
 /// Consumer: optional
 ///
@@ -13,12 +44,22 @@ use usos_core::client::CLIENT;
 ///
 /// SSL: not required
 pub async fn search_faculties(
+    client: &Client,
     language: Language,
     query: &str,
     visibility: Option<Visibility>,
     num: Option<SearchResults>,
     start: Option<StartIndex>,
-) {
+) -> usos_core::Result<FacultySearch> {
+    client
+        .call(&SearchFaculties {
+            language,
+            query,
+            visibility,
+            num,
+            start,
+        })
+        .await
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,7 +70,7 @@ pub struct FacultySearch {
 
 #[derive(Debug, Deserialize)]
 pub struct FacultySearchItem {
-    pub id: String,
+    pub id: FacultyId,
     #[serde(rename = "match")]
     pub match_string: String,
 }
@@ -40,7 +81,7 @@ impl TryFrom<u8> for SearchResults {
     type Error = String;
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        if value > 20 {
+        if !(1..=20).contains(&value) {
             Err(String::from("Number should be in range <1, 20>"))
         } else {
             Ok(SearchResults(value))
@@ -62,6 +103,7 @@ impl TryFrom<u16> for StartIndex {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Visibility {
     Public,
     All,
@@ -76,9 +118,43 @@ impl Display for Visibility {
     }
 }
 
+#[test]
+fn search_results_are_validated() {
+    assert!(SearchResults::try_from(0).is_err());
+    assert!(SearchResults::try_from(1).is_ok());
+    assert!(SearchResults::try_from(20).is_ok());
+    assert!(SearchResults::try_from(21).is_err());
+}
+
 #[tokio::test]
-#[ignore]
-async fn test_get_faculty() {
-    let faculty = search_faculties(Language::English, "Kwes", None, None, None).await;
-    println!("{faculty:?}");
+async fn faculties_are_searched() {
+    use crate::test_utils::{json_response, mock_client, usos_method};
+    use wiremock::{matchers::body_string, MockServer};
+
+    let server = MockServer::start().await;
+    usos_method("fac/search")
+        .and(body_string(
+            "lang=pl&num=5&query=informatyki&visibility=all",
+        ))
+        .respond_with(json_response(serde_json::json!({
+            "items": [{ "id": "W4N", "match": "Wydział <b>Informatyki</b> i Telekomunikacji" }],
+            "next_page": false
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let found = search_faculties(
+        &mock_client(&server),
+        Language::Polish,
+        "informatyki",
+        Some(Visibility::All),
+        Some(SearchResults::try_from(5).unwrap()),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(found.items[0].id, FacultyId::new("W4N"));
+    assert!(!found.next_page);
 }
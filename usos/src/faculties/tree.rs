@@ -0,0 +1,324 @@
+//! Hierarchy of the faculties of an institution.
+
+use futures::{future::BoxFuture, stream, FutureExt, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::{collections::HashSet, fmt::Write, sync::Mutex};
+use usos_core::{
+    api::types::{
+        ids::FacultyId,
+        language::{Language, LanguageDictionary},
+    },
+    client::Client,
+};
+
+use super::{
+    faculty::{get_faculty, get_subfaculties, FacultyReference},
+    search::Visibility,
+};
+use crate::server_info::installation::Installation;
+
+/// Maximum number of `fac/subfaculties` requests sent at once.
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// Tree of faculties, rooted at the institution or any of its faculties.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacultyTree {
+    pub root: FacultyNode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacultyNode {
+    pub id: FacultyId,
+    pub name: LanguageDictionary,
+    pub children: Vec<FacultyNode>,
+}
+
+/// Walks the hierarchy with `fac/subfaculties`, see [`FacultyTree::builder`].
+#[derive(Debug, Clone)]
+pub struct FacultyTreeBuilder {
+    root: FacultyId,
+    visibility: Visibility,
+    max_depth: Option<usize>,
+}
+
+impl FacultyTreeBuilder {
+    pub fn new(root: FacultyId) -> Self {
+        Self {
+            root,
+            visibility: Visibility::Public,
+            max_depth: None,
+        }
+    }
+
+    /// Sets which faculties are included, only the public ones by default.
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Limits the depth of the tree, e.g. `1` gives the root and its direct subfaculties. Unlimited by default.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub async fn build(self, client: &Client) -> usos_core::Result<FacultyTree> {
+        let root = get_faculty(client, &self.root).await?;
+        let root = FacultyReference {
+            id: root.id,
+            name: root.name,
+        };
+        let visited = Mutex::new(HashSet::from([root.id.clone()]));
+        Ok(FacultyTree {
+            root: self.walk(client, &visited, root, 0).await?,
+        })
+    }
+
+    /// Faculties already in `visited` are skipped, so a cyclic hierarchy is not walked forever.
+    fn walk<'a>(
+        &'a self,
+        client: &'a Client,
+        visited: &'a Mutex<HashSet<FacultyId>>,
+        faculty: FacultyReference,
+        depth: usize,
+    ) -> BoxFuture<'a, usos_core::Result<FacultyNode>> {
+        async move {
+            let children = if self.max_depth.is_some_and(|max| depth >= max) {
+                Vec::new()
+            } else {
+                let mut subfaculties =
+                    get_subfaculties(client, &faculty.id, self.visibility).await?;
+                {
+                    let mut visited = visited.lock().unwrap();
+                    subfaculties.retain(|subfaculty| visited.insert(subfaculty.id.clone()));
+                }
+                stream::iter(subfaculties)
+                    .map(|subfaculty| self.walk(client, visited, subfaculty, depth + 1))
+                    .buffered(CONCURRENT_REQUESTS)
+                    .try_collect()
+                    .await?
+            };
+
+            Ok(FacultyNode {
+                id: faculty.id,
+                name: faculty.name,
+                children,
+            })
+        }
+        .boxed()
+    }
+}
+
+impl FacultyTree {
+    pub fn builder(root: FacultyId) -> FacultyTreeBuilder {
+        FacultyTreeBuilder::new(root)
+    }
+
+    /// Starts the tree at the institution of the installation.
+    pub fn builder_for(installation: &Installation) -> FacultyTreeBuilder {
+        FacultyTreeBuilder::new(installation.primary_faculty.id.clone())
+    }
+
+    /// Iterates over all faculties, depth-first.
+    pub fn iter(&self) -> impl Iterator<Item = &FacultyNode> {
+        let mut stack = vec![&self.root];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    pub fn find(&self, id: &FacultyId) -> Option<&FacultyNode> {
+        self.iter().find(|node| &node.id == id)
+    }
+
+    pub fn to_json(&self) -> usos_core::Result<String> {
+        Ok(serde_json::to_string_pretty(&self.root)?)
+    }
+
+    /// Exports the tree in the DOT language of Graphviz, with the names in the given language.
+    pub fn to_graphviz(&self, language: &Language) -> String {
+        let mut dot = String::from("digraph faculties {\n    node [shape=box];\n");
+        for node in self.iter() {
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\"];",
+                escape(node.id.as_str()),
+                escape(node.name.get(language))
+            );
+        }
+        for node in self.iter() {
+            for child in &node.children {
+                let _ = writeln!(
+                    dot,
+                    "    \"{}\" -> \"{}\";",
+                    escape(node.id.as_str()),
+                    escape(child.id.as_str())
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::{
+        faculties::faculty::tests::faculty,
+        test_utils::{json_response, mock_client, usos_method},
+    };
+
+    fn reference(id: &str, name: &str) -> serde_json::Value {
+        json!({ "id": id, "name": { "pl": name, "en": null } })
+    }
+
+    async fn mount_subfaculties(server: &MockServer, id: &str, children: serde_json::Value) {
+        usos_method("fac/subfaculties")
+            .and(body_string_contains(format!("fac_id={id}&")))
+            .respond_with(json_response(children))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn server() -> MockServer {
+        let server = MockServer::start().await;
+        usos_method("fac/faculty")
+            .respond_with(json_response(faculty(
+                "00000000",
+                "Politechnika Wrocławska",
+            )))
+            .mount(&server)
+            .await;
+        mount_subfaculties(
+            &server,
+            "00000000",
+            json!([
+                reference("W4N", "Wydział Informatyki i Telekomunikacji"),
+                reference("W8N", "Wydział \"Zarządzania\""),
+            ]),
+        )
+        .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn hierarchy_is_walked() {
+        let server = server().await;
+        mount_subfaculties(
+            &server,
+            "W4N",
+            json!([reference("K1", "Katedra Informatyki")]),
+        )
+        .await;
+        mount_subfaculties(&server, "W8N", json!([])).await;
+        mount_subfaculties(&server, "K1", json!([])).await;
+
+        let tree = FacultyTree::builder(FacultyId::new("00000000"))
+            .build(&mock_client(&server))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tree.iter().map(|node| node.id.as_str()).collect::<Vec<_>>(),
+            ["00000000", "W4N", "K1", "W8N"]
+        );
+        assert_eq!(
+            tree.find(&FacultyId::new("K1")).unwrap().name.polish(),
+            "Katedra Informatyki"
+        );
+    }
+
+    #[tokio::test]
+    async fn depth_is_limited() {
+        let server = server().await;
+
+        let tree = FacultyTree::builder(FacultyId::new("00000000"))
+            .with_max_depth(1)
+            .build(&mock_client(&server))
+            .await
+            .unwrap();
+
+        assert_eq!(tree.iter().count(), 3);
+        assert!(server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .all(|request| !String::from_utf8_lossy(&request.body).contains("fac_id=W4N")));
+    }
+
+    #[tokio::test]
+    async fn cycles_are_not_followed() {
+        let server = server().await;
+        mount_subfaculties(
+            &server,
+            "W4N",
+            json!([
+                reference("00000000", "Politechnika Wrocławska"),
+                reference("W8N", "Wydział \"Zarządzania\""),
+                reference("K1", "Katedra Informatyki"),
+            ]),
+        )
+        .await;
+        mount_subfaculties(
+            &server,
+            "K1",
+            json!([reference("W4N", "Wydział Informatyki i Telekomunikacji")]),
+        )
+        .await;
+        mount_subfaculties(&server, "W8N", json!([])).await;
+
+        let tree = FacultyTree::builder(FacultyId::new("00000000"))
+            .build(&mock_client(&server))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tree.iter().map(|node| node.id.as_str()).collect::<Vec<_>>(),
+            ["00000000", "W4N", "K1", "W8N"]
+        );
+    }
+
+    #[test]
+    fn tree_is_exported() {
+        let tree = FacultyTree {
+            root: FacultyNode {
+                id: FacultyId::new("00000000"),
+                name: LanguageDictionary::from_iter([(
+                    Language::Polish,
+                    String::from("Politechnika Wrocławska"),
+                )]),
+                children: vec![FacultyNode {
+                    id: FacultyId::new("W8N"),
+                    name: LanguageDictionary::from_iter([(
+                        Language::Polish,
+                        String::from("Wydział \"Zarządzania\""),
+                    )]),
+                    children: vec![],
+                }],
+            },
+        };
+
+        assert_eq!(
+            tree.to_graphviz(&Language::Polish),
+            "digraph faculties {\n    node [shape=box];\n    \
+             \"00000000\" [label=\"Politechnika Wrocławska\"];\n    \
+             \"W8N\" [label=\"Wydział \\\"Zarządzania\\\"\"];\n    \
+             \"00000000\" -> \"W8N\";\n}\n"
+        );
+        let json = serde_json::from_str::<serde_json::Value>(&tree.to_json().unwrap()).unwrap();
+        assert_eq!(json["children"][0]["id"], "W8N");
+        assert_eq!(json["name"]["pl"], "Politechnika Wrocławska");
+    }
+}
@@ -0,0 +1,190 @@
+//! Buildings and their locations.
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::{ParamString, Params},
+        selector::UsosFields,
+        types::{ids::BuildingId, language::LanguageDictionary},
+    },
+    client::Client,
+};
+
+use crate::tt::Activity;
+
+/// Mean radius of the Earth in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+/// Maximum number of `geo/building2` requests sent at once.
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// Geographical coordinates, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinates {
+    #[serde(rename = "lat")]
+    pub latitude: f64,
+    #[serde(rename = "long")]
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    pub fn new(latitude: f64, longitude: f64) -> Self {
+        Self {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Great-circle distance in meters, computed with the haversine formula.
+    pub fn distance_to(&self, other: &Coordinates) -> f64 {
+        let (lat1, lat2) = (self.latitude.to_radians(), other.latitude.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.longitude - self.longitude).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, UsosFields)]
+pub struct Building {
+    pub id: BuildingId,
+    pub name: LanguageDictionary,
+    pub campus_name: Option<LanguageDictionary>,
+    pub postal_address: Option<String>,
+    /// [`None`] if the installation does not know where the building is.
+    pub location: Option<Coordinates>,
+}
+
+struct GetBuilding<'a>(&'a BuildingId);
+
+impl Endpoint for GetBuilding<'_> {
+    const PATH: &'static str = "geo/building2";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = Building;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from([
+            ("building_id", ParamString::from(self.0)),
+            ("fields", Building::selector().into()),
+        ]))
+    }
+}
+
+/// geo/building2
+///
+/// Consumer: ignored
+///
+/// Token: ignored
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_building(
+    client: &Client,
+    building_id: &BuildingId,
+) -> usos_core::Result<Building> {
+    client.call(&GetBuilding(building_id)).await
+}
+
+/// Fetches the buildings concurrently with `geo/building2`, skipping duplicate IDs.
+pub async fn get_buildings<'a>(
+    client: &Client,
+    building_ids: impl IntoIterator<Item = &'a BuildingId>,
+) -> usos_core::Result<Vec<Building>> {
+    let building_ids = building_ids.into_iter().collect::<BTreeSet<_>>();
+    stream::iter(building_ids)
+        .map(|building_id| get_building(client, building_id))
+        .buffered(CONCURRENT_REQUESTS)
+        .try_collect()
+        .await
+}
+
+/// Locations of the buildings with a known location, e.g. for a
+/// [`TravelCheck`](crate::tt::analysis::TravelCheck).
+pub fn locations<'a>(
+    buildings: impl IntoIterator<Item = &'a Building>,
+) -> HashMap<BuildingId, Coordinates> {
+    buildings
+        .into_iter()
+        .filter_map(|building| Some((building.id.clone(), building.location?)))
+        .collect()
+}
+
+/// Fetches the locations of all buildings the activities take place in.
+pub async fn get_activity_locations(
+    client: &Client,
+    activities: &[Activity],
+) -> usos_core::Result<HashMap<BuildingId, Coordinates>> {
+    let building_ids = activities
+        .iter()
+        .filter_map(|activity| activity.building_id.as_ref());
+    let buildings = get_buildings(client, building_ids).await?;
+    Ok(locations(&buildings))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::{json, Value};
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_client, usos_method};
+
+    /// `geo/building2` response of a building at the given location.
+    pub(crate) fn building(id: &str, location: Option<(f64, f64)>) -> Value {
+        json!({
+            "id": id,
+            "name": { "pl": format!("Budynek {id}"), "en": format!("Building {id}") },
+            "campus_name": { "pl": "Kampus Główny", "en": "Main Campus" },
+            "postal_address": "Wybrzeże Wyspiańskiego 27, 50-370 Wrocław",
+            "location": location.map(|(lat, long)| json!({ "lat": lat, "long": long }))
+        })
+    }
+
+    #[test]
+    fn distances_are_computed() {
+        // the main building of Wrocław University of Science and Technology and the Market Square in Wrocław
+        let university = Coordinates::new(51.1071, 17.0617);
+        let market_square = Coordinates::new(51.1100, 17.0320);
+
+        let distance = university.distance_to(&market_square);
+
+        assert!((2050.0..2150.0).contains(&distance), "{distance}");
+        assert_eq!(university.distance_to(&university), 0.0);
+    }
+
+    #[tokio::test]
+    async fn locations_of_activities_are_fetched() {
+        let server = MockServer::start().await;
+        for (id, location) in [("C-13", Some((51.1071, 17.0617))), ("D-1", None)] {
+            usos_method("geo/building2")
+                .and(body_string_contains(format!("building_id={id}&")))
+                .and(body_string_contains("fields=id%7Cname%7Ccampus_name"))
+                .respond_with(json_response(building(id, location)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let activities = ["C-13", "D-1", "C-13"]
+            .into_iter()
+            .map(|building_id| {
+                let mut json =
+                    crate::tt::tests::lecture(1, "2024-10-01 07:30:00", "2024-10-01 09:00:00");
+                json["building_id"] = building_id.into();
+                Activity::deserialize(json).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let locations = get_activity_locations(&mock_client(&server), &activities)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            locations,
+            HashMap::from([(BuildingId::new("C-13"), Coordinates::new(51.1071, 17.0617))])
+        );
+    }
+}
@@ -0,0 +1,702 @@
+//! iCalendar ([RFC 5545](https://datatracker.ietf.org/doc/html/rfc5545)) export, e.g. for subscribing to the academic
+//! calendar or a timetable in Google Calendar or Thunderbird.
+//!
+//! Times are written in the `Europe/Warsaw` time zone, defined by a `VTIMEZONE` block built from the Polish DST rules
+//! (see [`usos_core::api::types::time`]), as USOS API provides them in the local Polish time.
+
+use std::fmt::{self, Display, Formatter, Write};
+use time::{
+    format_description::BorrowedFormatItem, macros::format_description, Date, Duration,
+    OffsetDateTime, UtcOffset,
+};
+use usos_core::api::types::{
+    language::{Language, LanguageDictionary},
+    time::{UsosDateTime, DST_OFFSET, STANDARD_OFFSET},
+};
+
+use crate::{calendar::CalendarEvent, tt::Activity};
+
+/// Identifier of the time zone of the exported times.
+pub const TIME_ZONE: &str = "Europe/Warsaw";
+
+const PRODUCT_ID: &str = "-//usos-rs//usos//EN";
+/// Maximum length of a line in octets, excluding the line break.
+const MAX_LINE_LENGTH: usize = 75;
+
+const DATE: &[BorrowedFormatItem<'_>] = format_description!("[year][month][day]");
+const DATE_TIME: &[BorrowedFormatItem<'_>] =
+    format_description!("[year][month][day]T[hour][minute][second]");
+
+/// When an event takes place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventTime {
+    /// Whole days, both dates are included.
+    AllDay { start: Date, end: Date },
+    /// Local Polish time, as provided by USOS API.
+    Timed {
+        start: UsosDateTime,
+        end: UsosDateTime,
+    },
+}
+
+/// Something that can be exported as a `VEVENT`.
+pub trait IcsEvent {
+    /// Identifier that stays the same when the event is exported again, derived from USOS IDs, e.g. `calendar-1234`.
+    ///
+    /// It is made globally unique by appending the domain of the installation.
+    fn uid(&self) -> String;
+
+    fn summary(&self) -> &LanguageDictionary;
+
+    fn time(&self) -> EventTime;
+
+    fn location(&self) -> Option<LanguageDictionary> {
+        None
+    }
+
+    fn description(&self) -> Option<LanguageDictionary> {
+        None
+    }
+}
+
+/// Builds a text in each of the languages of USOS API, [`None`] if there is no text in any of them.
+fn localized(text: impl Fn(&Language) -> Option<String>) -> Option<LanguageDictionary> {
+    let dictionary = [Language::Polish, Language::English]
+        .into_iter()
+        .filter_map(|language| Some((language.clone(), text(&language)?)))
+        .collect::<LanguageDictionary>();
+    (!dictionary.is_empty()).then_some(dictionary)
+}
+
+impl IcsEvent for CalendarEvent {
+    fn uid(&self) -> String {
+        format!("calendar-{}", self.id)
+    }
+
+    fn summary(&self) -> &LanguageDictionary {
+        &self.name
+    }
+
+    fn time(&self) -> EventTime {
+        EventTime::AllDay {
+            start: self.start_date.0.date(),
+            end: self.end_date.0.date(),
+        }
+    }
+
+    fn description(&self) -> Option<LanguageDictionary> {
+        self.faculty.as_ref().map(|faculty| faculty.name.clone())
+    }
+}
+
+impl IcsEvent for Activity {
+    /// Built from the start and the class group, course or room of the activity, which together identify it.
+    fn uid(&self) -> String {
+        let start = self.start_time.0.format(DATE_TIME).unwrap_or_default();
+        let subject = match (&self.unit_id, &self.group_number, &self.course_id) {
+            (Some(unit_id), Some(group_number), _) => format!("{unit_id}-{group_number}"),
+            (Some(unit_id), None, _) => unit_id.to_string(),
+            (None, _, Some(course_id)) => course_id.to_string(),
+            (None, _, None) => self
+                .room_id
+                .as_ref()
+                .map_or_else(|| String::from("none"), ToString::to_string),
+        };
+        format!("tt-{}-{subject}-{start}", self.activity_type)
+    }
+
+    fn summary(&self) -> &LanguageDictionary {
+        &self.name
+    }
+
+    fn time(&self) -> EventTime {
+        EventTime::Timed {
+            start: self.start_time,
+            end: self.end_time,
+        }
+    }
+
+    /// The building and the room number, e.g. `Budynek C-13, 0.31`.
+    fn location(&self) -> Option<LanguageDictionary> {
+        localized(|language| {
+            let building = self.building_name.as_ref().map(|name| name.get(language));
+            match (building, &self.room_number) {
+                (Some(building), Some(room)) => Some(format!("{building}, {room}")),
+                (building, room) => building.map(str::to_string).or_else(|| room.clone()),
+            }
+        })
+    }
+
+    /// The type of the classes, the group number and the IDs of the lecturers, e.g. `Wykład, gr. 1` and
+    /// `ID prowadzących: 42` in separate lines.
+    fn description(&self) -> Option<LanguageDictionary> {
+        localized(|language| {
+            let classtype = self
+                .classtype_name
+                .as_ref()
+                .map(|name| name.get(language).to_string());
+            let group = self.group_number.map(|number| format!("gr. {number}"));
+            let classes = classtype.into_iter().chain(group).collect::<Vec<_>>();
+            let lecturers = self
+                .lecturer_ids
+                .as_ref()
+                .filter(|ids| !ids.is_empty())
+                .map(|ids| {
+                    let label = match language {
+                        Language::Polish => "ID prowadzących",
+                        _ => "Lecturer IDs",
+                    };
+                    let ids = ids.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    format!("{label}: {}", ids.join(", "))
+                });
+            let lines = (!classes.is_empty())
+                .then(|| classes.join(", "))
+                .into_iter()
+                .chain(lecturers)
+                .collect::<Vec<_>>();
+            (!lines.is_empty()).then(|| lines.join("\n"))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VEvent {
+    uid: String,
+    summary: LanguageDictionary,
+    time: EventTime,
+    location: Option<LanguageDictionary>,
+    description: Option<LanguageDictionary>,
+}
+
+/// iCalendar file, written with [`Display`].
+#[derive(Debug, Clone)]
+pub struct IcsCalendar {
+    domain: String,
+    name: Option<String>,
+    languages: Vec<Language>,
+    timestamp: OffsetDateTime,
+    events: Vec<VEvent>,
+}
+
+impl IcsCalendar {
+    /// Creates an empty calendar. The domain (usually the host of the installation) is appended to the UIDs of events.
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            name: None,
+            languages: vec![Language::Polish, Language::English],
+            timestamp: OffsetDateTime::now_utc(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Sets the name shown by calendar applications.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the languages of the summaries, locations and descriptions, Polish and English by default.
+    ///
+    /// The distinct texts in these languages are joined with ` / `, e.g. `Wykład / Lecture`, or with a blank line in
+    /// the case of descriptions.
+    pub fn with_languages(mut self, languages: impl IntoIterator<Item = Language>) -> Self {
+        self.languages = languages.into_iter().collect();
+        self
+    }
+
+    /// Sets the `DTSTAMP` of events, the current time by default.
+    pub fn with_timestamp(mut self, timestamp: OffsetDateTime) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    pub fn push(&mut self, event: &impl IcsEvent) {
+        self.events.push(VEvent {
+            uid: event.uid(),
+            summary: event.summary().clone(),
+            time: event.time(),
+            location: event.location(),
+            description: event.description(),
+        });
+    }
+
+    pub fn with_events<'a, E: IcsEvent + 'a>(
+        mut self,
+        events: impl IntoIterator<Item = &'a E>,
+    ) -> Self {
+        for event in events {
+            self.push(event);
+        }
+        self
+    }
+
+    /// Joins the texts in the languages of the calendar, returning the language of the text if it is in a single one.
+    fn localize(&self, text: &LanguageDictionary, separator: &str) -> (String, Option<&Language>) {
+        let mut texts = Vec::<(&Language, &str)>::new();
+        for language in &self.languages {
+            if let Some(text) = text.try_get(language) {
+                if !texts.iter().any(|(_, other)| *other == text) {
+                    texts.push((language, text));
+                }
+            }
+        }
+        match texts.as_slice() {
+            // fall back to any language
+            [] => (
+                text.get(self.languages.first().unwrap_or(&Language::English))
+                    .to_string(),
+                None,
+            ),
+            [(language, text)] => (text.to_string(), Some(language)),
+            _ => (
+                texts
+                    .iter()
+                    .map(|(_, text)| *text)
+                    .collect::<Vec<_>>()
+                    .join(separator),
+                None,
+            ),
+        }
+    }
+
+    /// Content line of a localized property, with the `LANGUAGE` parameter only if the text is in a single language.
+    fn localized_line(&self, name: &str, text: &LanguageDictionary, separator: &str) -> String {
+        let (text, language) = self.localize(text, separator);
+        match language {
+            Some(language) => format!("{name};LANGUAGE={language}:{}", escape(&text)),
+            None => format!("{name}:{}", escape(&text)),
+        }
+    }
+}
+
+impl Display for IcsCalendar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut out = ContentWriter(f);
+        out.line("BEGIN:VCALENDAR")?;
+        out.line("VERSION:2.0")?;
+        out.line(&format!("PRODID:{PRODUCT_ID}"))?;
+        out.line("CALSCALE:GREGORIAN")?;
+        if let Some(name) = &self.name {
+            out.line(&format!("X-WR-CALNAME:{}", escape(name)))?;
+        }
+        out.line(&format!("X-WR-TIMEZONE:{TIME_ZONE}"))?;
+        write_time_zone(&mut out)?;
+
+        let timestamp = self
+            .timestamp
+            .to_offset(UtcOffset::UTC)
+            .format(DATE_TIME)
+            .map_err(|_| fmt::Error)?;
+        for event in &self.events {
+            out.line("BEGIN:VEVENT")?;
+            out.line(&format!(
+                "UID:{}@{}",
+                escape(&event.uid),
+                escape(&self.domain)
+            ))?;
+            out.line(&format!("DTSTAMP:{timestamp}Z"))?;
+            match event.time {
+                EventTime::AllDay { start, end } => {
+                    let end = end.max(start) + Duration::days(1);
+                    out.line(&format!(
+                        "DTSTART;VALUE=DATE:{}",
+                        start.format(DATE).map_err(|_| fmt::Error)?
+                    ))?;
+                    out.line(&format!(
+                        "DTEND;VALUE=DATE:{}",
+                        end.format(DATE).map_err(|_| fmt::Error)?
+                    ))?;
+                }
+                EventTime::Timed { start, end } => {
+                    let end = end.max(start);
+                    out.line(&format!(
+                        "DTSTART;TZID={TIME_ZONE}:{}",
+                        start.0.format(DATE_TIME).map_err(|_| fmt::Error)?
+                    ))?;
+                    out.line(&format!(
+                        "DTEND;TZID={TIME_ZONE}:{}",
+                        end.0.format(DATE_TIME).map_err(|_| fmt::Error)?
+                    ))?;
+                }
+            }
+            out.line(&self.localized_line("SUMMARY", &event.summary, " / "))?;
+            if let Some(location) = &event.location {
+                out.line(&self.localized_line("LOCATION", location, " / "))?;
+            }
+            if let Some(description) = &event.description {
+                out.line(&self.localized_line("DESCRIPTION", description, "\n\n"))?;
+            }
+            out.line("END:VEVENT")?;
+        }
+        out.line("END:VCALENDAR")
+    }
+}
+
+/// Writes the `Europe/Warsaw` time zone: DST from 02:00 of the last Sunday in March until 03:00 of the last Sunday in October.
+fn write_time_zone(out: &mut ContentWriter<'_, '_>) -> fmt::Result {
+    let standard = format_offset(STANDARD_OFFSET);
+    let daylight = format_offset(DST_OFFSET);
+    out.line("BEGIN:VTIMEZONE")?;
+    out.line(&format!("TZID:{TIME_ZONE}"))?;
+    out.line("BEGIN:DAYLIGHT")?;
+    out.line(&format!("TZOFFSETFROM:{standard}"))?;
+    out.line(&format!("TZOFFSETTO:{daylight}"))?;
+    out.line("TZNAME:CEST")?;
+    out.line("DTSTART:19700329T020000")?;
+    out.line("RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU")?;
+    out.line("END:DAYLIGHT")?;
+    out.line("BEGIN:STANDARD")?;
+    out.line(&format!("TZOFFSETFROM:{daylight}"))?;
+    out.line(&format!("TZOFFSETTO:{standard}"))?;
+    out.line("TZNAME:CET")?;
+    out.line("DTSTART:19701025T030000")?;
+    out.line("RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU")?;
+    out.line("END:STANDARD")?;
+    out.line("END:VTIMEZONE")
+}
+
+fn format_offset(offset: UtcOffset) -> String {
+    let (hours, minutes, _) = offset.as_hms();
+    let sign = if offset.is_negative() { '-' } else { '+' };
+    format!("{sign}{:02}{:02}", hours.abs(), minutes.abs())
+}
+
+/// Escapes a TEXT value.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes content lines terminated with CRLF, folding the ones longer than 75 octets.
+struct ContentWriter<'a, 'b>(&'a mut Formatter<'b>);
+
+impl ContentWriter<'_, '_> {
+    fn line(&mut self, line: &str) -> fmt::Result {
+        let mut length = 0;
+        for c in line.chars() {
+            if length + c.len_utf8() > MAX_LINE_LENGTH {
+                self.0.write_str("\r\n ")?;
+                // the leading space counts towards the limit
+                length = 1;
+            }
+            self.0.write_char(c)?;
+            length += c.len_utf8();
+        }
+        self.0.write_str("\r\n")
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ical::{property::Property, IcalParser};
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::{collections::HashSet, io::BufReader};
+    use time::macros::datetime;
+
+    use super::*;
+
+    /// Parses the file with the independent `ical` parser and checks the requirements of RFC 5545 it is lenient about.
+    pub(crate) fn validate(ics: &str) -> Result<(), String> {
+        if !ics.ends_with("\r\n") {
+            return Err(String::from("content must end with CRLF"));
+        }
+        for line in ics[..ics.len() - 2].split("\r\n") {
+            if line.contains('\n') || line.contains('\r') {
+                return Err(format!("bare line break in {line:?}"));
+            }
+            if line.len() > MAX_LINE_LENGTH {
+                return Err(format!("line longer than 75 octets: {line:?}"));
+            }
+        }
+
+        let mut parser = IcalParser::new(BufReader::new(ics.as_bytes()));
+        let calendar = parser
+            .next()
+            .ok_or("content must have a VCALENDAR")?
+            .map_err(|error| error.to_string())?;
+        if parser.next().is_some() {
+            return Err(String::from("content must have exactly one VCALENDAR"));
+        }
+
+        let calendar_version = single(&calendar.properties, "VCALENDAR", "VERSION")?;
+        if calendar_version.value.as_deref() != Some("2.0") {
+            return Err(String::from("VERSION must be 2.0"));
+        }
+        single(&calendar.properties, "VCALENDAR", "PRODID")?;
+
+        let mut time_zones = HashSet::new();
+        for time_zone in &calendar.timezones {
+            let id = single(&time_zone.properties, "VTIMEZONE", "TZID")?;
+            time_zones.insert(id.value.clone().unwrap_or_default());
+            for transition in &time_zone.transitions {
+                for property in ["DTSTART", "TZOFFSETFROM", "TZOFFSETTO"] {
+                    single(&transition.properties, "STANDARD/DAYLIGHT", property)?;
+                }
+            }
+        }
+
+        for event in &calendar.events {
+            single(&event.properties, "VEVENT", "UID")?;
+            let timestamp = single(&event.properties, "VEVENT", "DTSTAMP")?;
+            if !timestamp
+                .value
+                .as_deref()
+                .unwrap_or_default()
+                .ends_with('Z')
+            {
+                return Err(String::from("DTSTAMP must be in UTC"));
+            }
+            let start = single(&event.properties, "VEVENT", "DTSTART")?;
+            for property in ["SUMMARY", "LOCATION", "DESCRIPTION", "DTEND"] {
+                if count(&event.properties, property) > 1 {
+                    return Err(format!("VEVENT must have at most one {property}"));
+                }
+            }
+            let end = event
+                .properties
+                .iter()
+                .find(|property| property.name == "DTEND");
+            if end.is_some_and(|end| end.value < start.value) {
+                return Err(String::from("DTEND before DTSTART"));
+            }
+            for property in [Some(start), end].into_iter().flatten() {
+                for (_, values) in property
+                    .params
+                    .iter()
+                    .flatten()
+                    .filter(|(name, _)| name == "TZID")
+                {
+                    if let Some(undefined) = values.iter().find(|id| !time_zones.contains(*id)) {
+                        return Err(format!("undefined time zone {undefined}"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn count(properties: &[Property], name: &str) -> usize {
+        properties
+            .iter()
+            .filter(|property| property.name == name)
+            .count()
+    }
+
+    fn single<'a>(
+        properties: &'a [Property],
+        component: &str,
+        name: &str,
+    ) -> Result<&'a Property, String> {
+        match count(properties, name) {
+            1 => Ok(properties
+                .iter()
+                .find(|property| property.name == name)
+                .unwrap()),
+            _ => Err(format!("{component} must have exactly one {name}")),
+        }
+    }
+
+    struct Lecture;
+
+    impl IcsEvent for Lecture {
+        fn uid(&self) -> String {
+            String::from("lecture-1")
+        }
+
+        fn summary(&self) -> &LanguageDictionary {
+            static SUMMARY: std::sync::OnceLock<LanguageDictionary> = std::sync::OnceLock::new();
+            SUMMARY.get_or_init(|| {
+                LanguageDictionary::from_iter([
+                    (
+                        Language::Polish,
+                        String::from("Analiza matematyczna 1; wykład"),
+                    ),
+                    (
+                        Language::English,
+                        String::from("Mathematical analysis 1, lecture"),
+                    ),
+                ])
+            })
+        }
+
+        fn time(&self) -> EventTime {
+            EventTime::Timed {
+                start: UsosDateTime(datetime!(2024-10-01 07:30)),
+                end: UsosDateTime(datetime!(2024-10-01 09:00)),
+            }
+        }
+
+        fn location(&self) -> Option<LanguageDictionary> {
+            Some(LanguageDictionary::from_iter([(
+                Language::Polish,
+                String::from("C-13, sala 0.31"),
+            )]))
+        }
+
+        fn description(&self) -> Option<LanguageDictionary> {
+            Some(LanguageDictionary::from_iter([(
+                Language::Polish,
+                String::from("Prowadzący: dr Jan Kowalski\nGrupa 1"),
+            )]))
+        }
+    }
+
+    fn christmas() -> CalendarEvent {
+        CalendarEvent::deserialize(json!({
+            "id": "1234",
+            "name": { "pl": "Ferie zimowe", "en": "Winter break" },
+            "start_date": "2024-12-23 00:00:00",
+            "end_date": "2025-01-06 00:00:00",
+            "faculty": null,
+            "type": "holidays",
+        }))
+        .unwrap()
+    }
+
+    fn calendar() -> IcsCalendar {
+        let mut calendar = IcsCalendar::new("apps.usos.pwr.edu.pl")
+            .with_name("Plan zajęć")
+            .with_timestamp(datetime!(2024-09-30 12:00 +2))
+            .with_events([&christmas()]);
+        calendar.push(&Lecture);
+        calendar
+    }
+
+    #[test]
+    fn export_is_valid() {
+        let ics = calendar().to_string();
+
+        assert_eq!(validate(&ics), Ok(()));
+    }
+
+    #[test]
+    fn events_are_exported() {
+        let ics = calendar().to_string();
+
+        assert!(ics.contains("\r\nUID:calendar-1234@apps.usos.pwr.edu.pl\r\n"));
+        assert!(ics.contains("\r\nDTSTAMP:20240930T100000Z\r\n"));
+        assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20241223\r\nDTEND;VALUE=DATE:20250107\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Ferie zimowe / Winter break\r\n"));
+        assert!(ics.contains(
+            "\r\nDTSTART;TZID=Europe/Warsaw:20241001T073000\r\nDTEND;TZID=Europe/Warsaw:20241001T090000\r\n"
+        ));
+        assert!(ics.contains("\r\nLOCATION;LANGUAGE=pl:C-13\\, sala 0.31\r\n"));
+        assert!(
+            ics.contains("\r\nDESCRIPTION;LANGUAGE=pl:Prowadzący: dr Jan Kowalski\\nGrupa 1\r\n")
+        );
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let ics = calendar()
+            .with_languages([Language::Polish, Language::English])
+            .to_string();
+
+        assert!(ics.contains("\r\nSUMMARY:Analiza matematyczna 1\\; wykład / Mathematical analysis 1\\, lectur\r\n e\r\n"));
+        assert_eq!(validate(&ics), Ok(()));
+
+        let calendar = IcalParser::new(BufReader::new(ics.as_bytes()))
+            .next()
+            .unwrap()
+            .unwrap();
+        let summary = calendar.events[1]
+            .properties
+            .iter()
+            .find(|property| property.name == "SUMMARY")
+            .unwrap();
+        assert_eq!(
+            summary.value.as_deref(),
+            Some("Analiza matematyczna 1\\; wykład / Mathematical analysis 1\\, lecture")
+        );
+    }
+
+    #[test]
+    fn single_language_summaries() {
+        let ics = calendar().with_languages([Language::English]).to_string();
+
+        assert!(ics.contains("\r\nSUMMARY;LANGUAGE=en:Winter break\r\n"));
+    }
+
+    #[test]
+    fn time_zone_follows_polish_dst_rules() {
+        use usos_core::api::types::time::warsaw_offset;
+
+        let ics = calendar().to_string();
+        assert!(ics.contains("BEGIN:DAYLIGHT\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\n"));
+        assert!(ics.contains("BEGIN:STANDARD\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\n"));
+
+        // the last Sundays of March and October 2024, at 02:00 and 03:00 local time
+        assert_eq!(
+            warsaw_offset(datetime!(2024-03-31 00:59 UTC)),
+            STANDARD_OFFSET
+        );
+        assert_eq!(warsaw_offset(datetime!(2024-03-31 01:00 UTC)), DST_OFFSET);
+        assert_eq!(warsaw_offset(datetime!(2024-10-27 00:59 UTC)), DST_OFFSET);
+        assert_eq!(
+            warsaw_offset(datetime!(2024-10-27 01:00 UTC)),
+            STANDARD_OFFSET
+        );
+    }
+
+    #[test]
+    fn timetable_activities_are_exported() {
+        let activity = Activity::deserialize(crate::tt::tests::lecture(
+            98765,
+            "2024-10-01 07:30:00",
+            "2024-10-01 09:00:00",
+        ))
+        .unwrap();
+        let ics = IcsCalendar::new("apps.usos.pwr.edu.pl")
+            .with_timestamp(datetime!(2024-09-30 12:00 +2))
+            .with_events([&activity])
+            .to_string();
+
+        assert_eq!(validate(&ics), Ok(()));
+        assert!(
+            ics.contains("\r\nUID:tt-classgroup-98765-1-20241001T073000@apps.usos.pwr.edu.pl\r\n")
+        );
+        assert!(ics.contains("\r\nDTSTART;TZID=Europe/Warsaw:20241001T073000\r\n"));
+        assert!(ics.contains("\r\nLOCATION:Budynek C-13\\, 0.31 / Building C-13\\, 0.31\r\n"));
+        assert!(ics.replace("\r\n ", "").contains(
+            "\r\nDESCRIPTION:Wykład\\, gr. 1\\nID prowadzących: 42\\n\\n\
+             Lecture\\, gr. 1\\nLecturer IDs: 42\r\n"
+        ));
+
+        let english = IcsCalendar::new("apps.usos.pwr.edu.pl")
+            .with_languages([Language::English])
+            .with_events([&activity])
+            .to_string();
+        assert!(english.contains("\r\nLOCATION;LANGUAGE=en:Building C-13\\, 0.31\r\n"));
+        assert!(
+            english.contains("\r\nDESCRIPTION;LANGUAGE=en:Lecture\\, gr. 1\\nLecturer IDs: 42\r\n")
+        );
+    }
+
+    #[test]
+    fn validator_rejects_invalid_content() {
+        assert!(validate("BEGIN:VCALENDAR\nEND:VCALENDAR\n").is_err());
+        assert!(validate("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nEND:VCALENDAR\r\n").is_err());
+        assert!(validate(&format!(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:x\r\nX-LONG:{}\r\nEND:VCALENDAR\r\n",
+            "a".repeat(80)
+        ))
+        .is_err());
+        assert!(validate(
+            "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:x\r\nBEGIN:VEVENT\r\nUID:1\r\nDTSTAMP:20240101T000000Z\r\n\
+             DTSTART;TZID=Europe/Warsaw:20240101T000000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n"
+        )
+        .is_err());
+    }
+}
@@ -1,5 +1,10 @@
 pub mod calendar;
 pub mod faculties;
+pub mod geo;
+pub mod ics;
 pub mod reference;
-pub mod server;
 pub mod server_info;
+pub mod tt;
+
+#[cfg(test)]
+mod test_utils;
@@ -1,4 +1,16 @@
 pub mod method;
 pub mod method_index;
 pub mod module;
+pub mod required_scopes;
 pub mod scopes;
+pub mod snapshot;
+pub mod tree;
+
+/// Prepends the `services/` prefix to a method or module name if it is missing.
+fn full_name(name: &str) -> String {
+    if name.starts_with("services/") {
+        name.to_string()
+    } else {
+        format!("services/{name}")
+    }
+}
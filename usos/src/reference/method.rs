@@ -1,6 +1,48 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+        selector::{Field as SelectorField, Selector, SelectorError, UsosFields},
+        types::scopes::Scope,
+    },
+    client::Client,
+};
+
+use super::full_name;
+
+struct GetMethod<'a> {
+    name: &'a str,
+    admin_access: bool,
+}
+
+impl Endpoint for GetMethod<'_> {
+    const PATH: &'static str = "apiref/method";
+    const CONSUMER: Requirement = Requirement::Optional;
+    type Token = token::Ignored;
+    type Response = MethodReference;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        let fields = MethodReference::selector()
+            .into_fields()
+            .into_iter()
+            .filter(|field| {
+                self.admin_access || *field != SelectorField::One(String::from("admin_access"))
+            })
+            .collect::<Selector>();
+
+        Ok(Params::from([
+            ("name", full_name(self.name)),
+            ("fields", fields.to_string()),
+        ]))
+    }
+}
 
-use usos_core::{api::types::scopes::Scope, client::CLIENT};
 /// apiref/method
 ///
 /// Consumer: optional (required only for `admin_access`)
@@ -10,12 +52,25 @@ use usos_core::{api::types::scopes::Scope, client::CLIENT};
 /// Scopes: n/a
 ///
 /// SSL: not required
-pub async fn get_method_info(method_name: &str) {}
+///
+/// Method names can be provided with or without the `services/` prefix.
+/// `admin_access` is requested only if the client has a consumer key.
+pub async fn get_method_info(
+    client: &Client,
+    method_name: &str,
+) -> usos_core::Result<MethodReference> {
+    client
+        .call(&GetMethod {
+            name: method_name,
+            admin_access: client.is_authorized(),
+        })
+        .await
+}
 
 #[tokio::test]
 #[ignore]
 async fn test_get_method_info() {
-    let method = get_method_info("services/apiref/method").await;
+    let method = get_method_info(&usos_core::client::CLIENT, "services/apiref/method").await;
     println!("{:?}", method);
 }
 
@@ -34,81 +89,226 @@ async fn test_get_method_info() {
 /// - [`Optional`] - you may include a Token, to achieve some special behavior (i.e. some methods allow you to pass **user_id** - or include an Access Token - both in order to identify a user),
 ///
 /// - [`Ignored`] - method doesn't care if you include a Token or not
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "lowercase")]
-enum SignatureRequirement {
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum SignatureRequirement {
     Required,
     Optional,
     Ignored,
+    /// Requirement not known to this crate, with its code.
+    Unknown(String),
+}
+
+impl Display for SignatureRequirement {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SignatureRequirement::Required => write!(f, "required"),
+            SignatureRequirement::Optional => write!(f, "optional"),
+            SignatureRequirement::Ignored => write!(f, "ignored"),
+            SignatureRequirement::Unknown(code) => write!(f, "{code}"),
+        }
+    }
+}
+
+impl From<&str> for SignatureRequirement {
+    fn from(s: &str) -> Self {
+        match s {
+            "required" => Self::Required,
+            "optional" => Self::Optional,
+            "ignored" => Self::Ignored,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for SignatureRequirement {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<SignatureRequirement> for String {
+    fn from(requirement: SignatureRequirement) -> Self {
+        requirement.to_string()
+    }
 }
 
-// name|short_name|description|brief_description|ref_url|auth_options|arguments|returns|errors|result_fields|beta|deprecated|admin_access|is_internal
-#[derive(Debug, Deserialize)]
+impl FromStr for SignatureRequirement {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, UsosFields)]
 pub struct MethodReference {
     /// name of the method
-    name: String,
+    pub name: String,
     /// name without a path
-    short_name: String,
+    pub short_name: String,
     /// HTML-formatted description of what the method does
-    description: String,
+    pub description: String,
     /// brief (max 80 characters), single-line, plain-text description of what the method does
-    brief_description: String,
+    pub brief_description: String,
     /// URL of a USOSap Reference webpage with method description
-    ref_url: String,
+    pub ref_url: String,
     /// describes authentication requirements for this method
-    auth_options: AuthRequirements,
+    pub auth_options: AuthRequirements,
     /// list of dictionaries describing method's parameters
-    arguments: Vec<Argument>,
+    pub arguments: Vec<Argument>,
     /// HTML-formatted description method's return value
-    returns: String,
+    pub returns: String,
     /// HTML-formatted description of possible method exceptions
-    errors: String,
+    pub errors: String,
     ///  list of method's result fields. Any field can belong to either primary or secondary section. This list serves as a concrete specification and an alternative for the "returns" field in the method description
-    result_fields: Vec<Field>,
+    pub result_fields: Vec<Field>,
     /// BETA methods may be altered in a backward-incompatible way
-    beta: bool,
+    pub beta: bool,
     /// in case of non-deprecated methods this will be null
-    deprecated: Option<Deprecated>,
+    pub deprecated: Option<Deprecated>,
     /// true if you have administrative access to this method. You need to sign the request with your Consumer Key in order to access this field.
     /// **Consumer key required!!!
-    admin_access: Option<bool>,
+    #[serde(default)]
+    pub admin_access: Option<bool>,
     /// true if this method is intended to be used only internally, by USOS API itself. This implies that it is in permanent BETA mode, and it can be altered or removed at any time.
-    is_internal: bool,
+    pub is_internal: bool,
+}
+
+impl MethodReference {
+    /// Checks that the selector selects only the fields returned by the method (see [`Selector::validate`]).
+    pub fn validate_selector(&self, selector: &Selector) -> Result<(), SelectorError> {
+        selector.validate(
+            self.result_fields
+                .iter()
+                .filter_map(|field| field.name.as_deref()),
+        )
+    }
 }
 
-// consumer|token|administrative_only|ssl_required|scopes
-#[derive(Debug, Deserialize)]
-struct AuthRequirements {
-    consumer: SignatureRequirement,
-    token: SignatureRequirement,
-    administrative_only: bool,
-    ssl_required: bool,
-    scopes: Vec<Scope>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthRequirements {
+    pub consumer: SignatureRequirement,
+    pub token: SignatureRequirement,
+    pub administrative_only: bool,
+    pub ssl_required: bool,
+    pub scopes: Vec<Scope>,
 }
 
-// name|is_required|is_deprecated|default_value|description
-#[derive(Debug, Deserialize)]
-struct Argument {
-    name: String,
-    is_required: bool,
-    is_deprecated: bool,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Argument {
+    pub name: String,
+    pub is_required: bool,
+    pub is_deprecated: bool,
     /// [`None`] if parameter doesn't have a default value
-    default_value: Option<String>,
-    description: String,
+    pub default_value: Option<String>,
+    pub description: String,
 }
 
-// name|description|is_primary|is_secondary
-#[derive(Debug, Deserialize)]
-struct Field {
-    name: Option<String>,
-    description: String,
-    is_primary: bool,
-    is_secondary: bool,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Field {
+    /// USOS API sends `null` for some of the fields
+    pub name: Option<String>,
+    pub description: String,
+    pub is_primary: bool,
+    pub is_secondary: bool,
 }
 
-// deprecated_by|present_until
-#[derive(Debug, Deserialize)]
-struct Deprecated {
-    deprecated_by: Option<String>,
-    present_until: Option<String>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deprecated {
+    pub deprecated_by: Option<String>,
+    pub present_until: Option<String>,
+}
+
+#[test]
+fn unknown_signature_requirements_keep_their_code() {
+    let requirement = SignatureRequirement::deserialize(serde_json::json!("sometimes")).unwrap();
+
+    assert_eq!(
+        requirement,
+        SignatureRequirement::Unknown(String::from("sometimes"))
+    );
+    assert_eq!(
+        serde_json::to_value(requirement).unwrap(),
+        serde_json::json!("sometimes")
+    );
+    assert_eq!(
+        SignatureRequirement::deserialize(serde_json::json!("required")).unwrap(),
+        SignatureRequirement::Required
+    );
+}
+
+#[test]
+fn selector_is_validated_against_result_fields() {
+    let field = |name: &str| {
+        serde_json::json!({
+            "name": name,
+            "description": "",
+            "is_primary": true,
+            "is_secondary": false,
+        })
+    };
+    let method = MethodReference::deserialize(serde_json::json!({
+        "name": "services/users/user",
+        "short_name": "user",
+        "description": "",
+        "brief_description": "",
+        "ref_url": "",
+        "auth_options": {
+            "consumer": "optional",
+            "token": "optional",
+            "administrative_only": false,
+            "ssl_required": false,
+            "scopes": [],
+        },
+        "arguments": [],
+        "returns": "",
+        "errors": "",
+        "result_fields": [field("id"), field("first_name"), field("faculty")],
+        "beta": false,
+        "deprecated": null,
+        "is_internal": false,
+    }))
+    .unwrap();
+
+    assert!(method
+        .validate_selector(&"id|faculty[id|name]".parse().unwrap())
+        .is_ok());
+    assert_eq!(
+        method.validate_selector(&"id|last_name".parse().unwrap()),
+        Err(SelectorError::UnknownField(String::from("last_name")))
+    );
+}
+
+#[tokio::test]
+async fn admin_access_is_requested_only_with_consumer_key() {
+    use crate::test_utils::{json_response, mock_authorized_client, mock_client, usos_method};
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    let server = MockServer::start().await;
+    let mut reference = crate::reference::tree::tests::method("services/tt/user");
+    reference["admin_access"] = serde_json::json!(false);
+    usos_method("apiref/method")
+        .and(body_string_contains("admin_access"))
+        .respond_with(json_response(reference))
+        .expect(1)
+        .mount(&server)
+        .await;
+    usos_method("apiref/method")
+        .respond_with(json_response(crate::reference::tree::tests::method(
+            "services/tt/user",
+        )))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let anonymous = get_method_info(&mock_client(&server), "tt/user")
+        .await
+        .unwrap();
+    let signed = get_method_info(&mock_authorized_client(&server), "tt/user")
+        .await
+        .unwrap();
+
+    assert_eq!(anonymous.admin_access, None);
+    assert_eq!(signed.admin_access, Some(false));
 }
@@ -1,5 +1,24 @@
 use serde::Deserialize;
-use usos_core::client::CLIENT;
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+    },
+    client::Client,
+};
+
+struct GetMethodIndex;
+
+impl Endpoint for GetMethodIndex {
+    const PATH: &'static str = "apiref/method_index";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = Vec<MethodBrief>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from(()))
+    }
+}
 
 /// apiref/method_index
 ///
@@ -10,18 +29,20 @@ use usos_core::client::CLIENT;
 /// Scopes: n/a
 ///
 /// SSL: not required
-pub async fn get_method_index() {}
+pub async fn get_method_index(client: &Client) -> usos_core::Result<Vec<MethodBrief>> {
+    client.call(&GetMethodIndex).await
+}
 
 #[tokio::test]
 #[ignore]
 async fn test_get_method_index() {
-    let methods = get_method_index().await;
+    let methods = get_method_index(&usos_core::client::CLIENT).await;
     println!("{methods:#?}");
 }
 
 #[derive(Debug, Deserialize)]
 pub struct MethodBrief {
-    /// Method name without "services/" prefix
+    /// Full method name, e.g. `services/apiref/method`
     pub name: String,
     pub brief_description: String,
 }
@@ -1,6 +1,32 @@
-use serde::Deserialize;
-use std::fmt::Display;
-use usos_core::client::CLIENT;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+    },
+    client::Client,
+};
+
+use super::{full_name, method_index::get_method_index};
+
+struct GetModule<'a>(&'a str);
+
+impl Endpoint for GetModule<'_> {
+    const PATH: &'static str = "apiref/module";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = ModuleInfo;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from(("name", full_name(self.0))))
+    }
+}
 
 /// apiref/module
 ///
@@ -11,136 +37,169 @@ use usos_core::client::CLIENT;
 /// Scopes: n/a
 ///
 /// SSL: not required
-pub async fn get_module_info(module_name: Module) {}
+pub async fn get_module_info(client: &Client, module: &Module) -> usos_core::Result<ModuleInfo> {
+    get_module_info_by_name(client, &module.to_string()).await
+}
+
+/// Same as [`get_module_info`], but accepts any module name, e.g. a submodule.
+///
+/// Module names can be provided with or without the `services/` prefix.
+pub async fn get_module_info_by_name(
+    client: &Client,
+    module_name: &str,
+) -> usos_core::Result<ModuleInfo> {
+    client.call(&GetModule(module_name)).await
+}
+
+/// Lists the top-level modules of the installation, based on its `apiref/method_index`.
+pub async fn get_modules(client: &Client) -> usos_core::Result<Vec<Module>> {
+    let methods = get_method_index(client).await?;
+    let names = methods
+        .iter()
+        .filter_map(|method| {
+            let name = method
+                .name
+                .strip_prefix("services/")
+                .unwrap_or(&method.name);
+            name.split_once('/').map(|(module, _)| module)
+        })
+        .collect::<BTreeSet<_>>();
+
+    Ok(names.into_iter().map(Module::from).collect())
+}
 
 #[tokio::test]
 #[ignore]
 async fn test_get_module_info() {
-    let module_info = get_module_info(Module::ApiReference).await;
+    use usos_core::client::CLIENT;
+
+    let module_info = get_module_info(&CLIENT, &Module::from("apiref")).await;
     println!("{:?}", module_info);
 }
 
-pub enum Module {
-    ApiReference,
-    ApiServerData,
-    ApiStats,
-    Attendance,
-    Blobbox,
-    Calendar,
-    Cards,
-    Courses,
-    Credits,
-    CourseTests,
-    CustomGroups,
-    StudentRecordsTransfer,
-    EventSubsription,
-    EventsEdition,
-    ExamReports,
-    ExamReporstExtra,
-    Exams,
-    Faculties,
-    FacultyPermissions,
-    FeedbackReports,
-    FileShare,
-    GeographicalData,
-    Grades,
-    Groups,
-    Guide,
-    Housing,
-    InstitutionalAdresses,
-    MailClient,
-    Mailing,
-    Meetings,
-    Mobility,
-    News,
-    OAuth,
-    OAuth2,
-    Payments,
-    Phones,
-    Photos,
-    Pit,
-    PlacementTests,
-    PrimaryGroups,
-    StudyPrograms,
-    Registrations,
-    ClearanceSlips,
-    Statements,
-    Surveys,
-    Terms,
-    Theses,
-    TimeTables,
-    UserPreferences,
-    Users,
+/// Top-level module of USOS API, e.g. `services/apiref`, stored by its short name, e.g. `apiref`.
+///
+/// Installations differ in the modules they provide, see [`get_modules`] for the list of modules available in an
+/// installation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Module(String);
+
+impl Module {
+    /// Short name of the module, without the `services/` prefix.
+    pub fn name(&self) -> &str {
+        &self.0
+    }
 }
 
 impl Display for Module {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Module::ApiReference => "apiref",
-                Module::ApiServerData => "apisrv",
-                Module::ApiStats => "apistlog",
-                Module::Attendance => "attendance",
-                Module::Blobbox => "blobbox",
-                Module::Calendar => "calendar",
-                Module::Cards => "cards",
-                Module::Courses => "courses",
-                Module::Credits => "credits",
-                Module::CourseTests => "crstests",
-                Module::CustomGroups => "csgroups",
-                Module::StudentRecordsTransfer => "emrex",
-                Module::EventSubsription => "events",
-                Module::EventsEdition => "events2",
-                Module::ExamReports => "examrep",
-                Module::ExamReporstExtra => "examrep2",
-                Module::Exams => "exams",
-                Module::Faculties => "fac",
-                Module::FacultyPermissions => "facperms",
-                Module::FeedbackReports => "feedback",
-                Module::FileShare => "fileshare",
-                Module::GeographicalData => "geo",
-                Module::Grades => "grades",
-                Module::Groups => "groups",
-                Module::Guide => "guide",
-                Module::Housing => "housing",
-                Module::InstitutionalAdresses => "instaddr",
-                Module::MailClient => "mailclient",
-                Module::Mailing => "mailing",
-                Module::Meetings => "meetings",
-                Module::Mobility => "mobility",
-                Module::News => "news",
-                Module::OAuth => "oauth",
-                Module::OAuth2 => "oauth2",
-                Module::Payments => "payments",
-                Module::Phones => "phones",
-                Module::Photos => "photos",
-                Module::Pit => "pit",
-                Module::PlacementTests => "plctests",
-                Module::PrimaryGroups => "prgroups",
-                Module::StudyPrograms => "progs",
-                Module::Registrations => "registrations",
-                Module::ClearanceSlips => "slips",
-                Module::Statements => "statements",
-                Module::Surveys => "surveys",
-                Module::Terms => "terms",
-                Module::Theses => "theses",
-                Module::TimeTables => "tt",
-                Module::UserPreferences => "uprefs",
-                Module::Users => "users",
-            }
-        )
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl From<&str> for Module {
+    fn from(name: &str) -> Self {
+        Self(name.strip_prefix("services/").unwrap_or(name).to_string())
+    }
+}
+
+impl From<String> for Module {
+    fn from(name: String) -> Self {
+        name.as_str().into()
+    }
+}
+
+impl From<Module> for String {
+    fn from(module: Module) -> Self {
+        module.0
+    }
+}
+
+impl FromStr for Module {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModuleInfo {
-    name: String,
-    title: String,
-    brief_description: String,
-    description: String,
-    submodules: Vec<String>,
-    methods: Vec<String>,
-    beta: bool,
+    /// Full name of the module, e.g. `services/apiref`
+    pub name: String,
+    pub title: String,
+    pub brief_description: String,
+    /// HTML-formatted description of the module
+    pub description: String,
+    /// Full names of the submodules
+    pub submodules: Vec<String>,
+    /// Full names of the methods
+    pub methods: Vec<String>,
+    pub beta: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_client, usos_method};
+
+    #[test]
+    fn module_names_round_trip() {
+        assert_eq!(Module::from("services/tt"), Module::from("tt"));
+        assert_eq!(Module::from("services/tt").name(), "tt");
+        assert_eq!(Module::from("hogwarts").to_string(), "hogwarts");
+        assert_eq!(
+            serde_json::from_value::<Module>(json!("services/tt")).unwrap(),
+            Module::from("tt")
+        );
+    }
+
+    #[tokio::test]
+    async fn modules_are_derived_from_method_index() {
+        let server = MockServer::start().await;
+        usos_method("apiref/method_index")
+            .respond_with(json_response(json!([
+                { "name": "services/apiref/method", "brief_description": "" },
+                { "name": "services/apiref/module", "brief_description": "" },
+                { "name": "services/tt/user", "brief_description": "" },
+                { "name": "services/hogwarts/sorting_hat/sort", "brief_description": "" }
+            ])))
+            .mount(&server)
+            .await;
+
+        let modules = get_modules(&mock_client(&server)).await.unwrap();
+
+        assert_eq!(modules, ["apiref", "hogwarts", "tt"].map(Module::from));
+    }
+
+    #[tokio::test]
+    async fn module_info_is_fetched() {
+        let server = MockServer::start().await;
+        usos_method("apiref/module")
+            .and(body_string_contains("name=services%2Ftt"))
+            .respond_with(json_response(json!({
+                "name": "services/tt",
+                "title": "Timetables",
+                "brief_description": "Timetables of users, rooms and groups",
+                "description": "<p>Timetables</p>",
+                "submodules": [],
+                "methods": ["services/tt/user", "services/tt/room"],
+                "beta": false,
+                "added_in": "future field"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let info = get_module_info(&mock_client(&server), &Module::from("tt"))
+            .await
+            .unwrap();
+
+        assert_eq!(info.name, "services/tt");
+        assert_eq!(info.methods.len(), 2);
+    }
 }
@@ -0,0 +1,186 @@
+use anyhow::anyhow;
+use usos_core::{api::types::scopes::Scopes, client::Client, errors::AppError};
+
+use super::{
+    full_name,
+    method::{get_method_info, MethodReference},
+    snapshot::ApiSnapshot,
+};
+
+/// Scopes needed to call a set of USOS API methods.
+///
+/// Computed from the `auth_options` of each method (see [`MethodReference`]).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequiredScopes {
+    /// The minimal set of scopes that allows calling all of the methods.
+    pub scopes: Scopes,
+    /// Methods that can be called only by administrative consumers - requesting scopes will not be enough to call these.
+    pub administrative_only: Vec<String>,
+}
+
+impl RequiredScopes {
+    /// Computes the required scopes from already fetched method references.
+    pub fn from_methods<'a>(methods: impl IntoIterator<Item = &'a MethodReference>) -> Self {
+        methods
+            .into_iter()
+            .fold(Self::default(), |mut acc, method| {
+                acc.scopes
+                    .extend(method.auth_options.scopes.iter().cloned());
+                if method.auth_options.administrative_only {
+                    acc.administrative_only.push(method.name.clone());
+                }
+                acc
+            })
+    }
+
+    /// Computes the required scopes from an [`ApiSnapshot`].
+    ///
+    /// This variant does not call USOS API, so it can be used at build time, e.g. with a snapshot embedded with
+    /// `include_str!` and parsed with [`ApiSnapshot::from_json`].
+    ///
+    /// Method names can be provided with or without the `services/` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of the methods is missing from the snapshot.
+    pub fn from_snapshot(
+        snapshot: &ApiSnapshot,
+        method_names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> usos_core::Result<Self> {
+        let selected = method_names
+            .into_iter()
+            .map(|name| {
+                let name = full_name(name.as_ref());
+                snapshot.tree.method(&name).ok_or_else(|| {
+                    AppError::Unexpected(anyhow!("Method {name} not found in the snapshot"))
+                })
+            })
+            .collect::<usos_core::Result<Vec<_>>>()?;
+
+        Ok(Self::from_methods(selected))
+    }
+}
+
+/// Fetches the references of the given methods from `services/apiref/method` and computes the scopes required to call them.
+///
+/// The result can be passed directly to [`acquire_request_token`](usos_core::api::auth::acquire_request_token),
+/// so that the user is not asked for more access than the application needs.
+///
+/// Method names can be provided with or without the `services/` prefix.
+pub async fn get_required_scopes(
+    client: &Client,
+    method_names: impl IntoIterator<Item = impl AsRef<str>>,
+) -> usos_core::Result<RequiredScopes> {
+    let mut methods = Vec::new();
+    for name in method_names {
+        methods.push(get_method_info(client, name.as_ref()).await?);
+    }
+
+    Ok(RequiredScopes::from_methods(&methods))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use usos_core::api::types::scopes::Scope;
+
+    use super::*;
+
+    fn method(name: &str, scopes: &[&str], administrative_only: bool) -> serde_json::Value {
+        json!({
+            "name": name,
+            "short_name": name.rsplit('/').next().unwrap(),
+            "description": "",
+            "brief_description": "",
+            "ref_url": "",
+            "auth_options": {
+                "consumer": "required",
+                "token": "required",
+                "administrative_only": administrative_only,
+                "ssl_required": false,
+                "scopes": scopes,
+            },
+            "arguments": [],
+            "returns": "",
+            "errors": "",
+            "result_fields": [],
+            "beta": false,
+            "deprecated": null,
+            "is_internal": false,
+        })
+    }
+
+    fn snapshot() -> ApiSnapshot {
+        crate::reference::snapshot::tests::snapshot(vec![
+            method("services/grades/terms2", &["grades"], false),
+            method("services/users/user", &["studies", "email"], false),
+            method("services/tt/user", &["studies"], false),
+            method("services/apisrv/admin_only", &[], true),
+        ])
+    }
+
+    #[test]
+    fn scopes_are_unioned() {
+        let required = RequiredScopes::from_snapshot(
+            &snapshot(),
+            ["services/grades/terms2", "users/user", "tt/user"],
+        )
+        .unwrap();
+
+        assert_eq!(
+            required.scopes,
+            Scopes::from_iter([Scope::Grades, Scope::Studies, Scope::Email])
+        );
+        assert!(required.administrative_only.is_empty());
+    }
+
+    #[test]
+    fn administrative_methods_are_flagged() {
+        let required =
+            RequiredScopes::from_snapshot(&snapshot(), ["apisrv/admin_only", "tt/user"]).unwrap();
+
+        assert_eq!(required.scopes, Scopes::from_iter([Scope::Studies]));
+        assert_eq!(
+            required.administrative_only,
+            vec!["services/apisrv/admin_only".to_string()]
+        );
+    }
+
+    #[test]
+    fn missing_method_fails() {
+        let res = RequiredScopes::from_snapshot(&snapshot(), ["foo/bar"]);
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn scopes_are_fetched() {
+        use crate::test_utils::{json_response, mock_client, usos_method};
+        use wiremock::{matchers::body_string_contains, MockServer};
+
+        let server = MockServer::start().await;
+        for (name, scopes) in [("grades/terms2", ["grades"]), ("tt/user", ["studies"])] {
+            usos_method("apiref/method")
+                .and(body_string_contains(format!(
+                    "name=services%2F{}",
+                    name.replace('/', "%2F")
+                )))
+                .respond_with(json_response(method(
+                    &format!("services/{name}"),
+                    &scopes,
+                    false,
+                )))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let required = get_required_scopes(&mock_client(&server), ["grades/terms2", "tt/user"])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            required.scopes,
+            Scopes::from_iter([Scope::Grades, Scope::Studies])
+        );
+    }
+}
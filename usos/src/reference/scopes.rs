@@ -1,5 +1,25 @@
 use serde::Deserialize;
-use usos_core::{api::types::scopes::Scope, client::CLIENT};
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+        types::scopes::Scope,
+    },
+    client::Client,
+};
+
+struct GetScopes;
+
+impl Endpoint for GetScopes {
+    const PATH: &'static str = "apiref/scopes";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = Vec<ApiScope>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from(()))
+    }
+}
 
 /// apiref/scopes
 ///
@@ -10,10 +30,10 @@ use usos_core::{api::types::scopes::Scope, client::CLIENT};
 /// Scopes: n/a
 ///
 /// SSL: not required
-pub async fn get_scopes() -> Vec<ApiScope> {
-    let response = CLIENT.builder("apiref/scopes").request().await.unwrap();
-
-    let mut scopes: Vec<ApiScope> = response.json().await.unwrap();
+///
+/// Whitespace in the descriptions is collapsed into single spaces.
+pub async fn get_scopes(client: &Client) -> usos_core::Result<Vec<ApiScope>> {
+    let mut scopes = client.call(&GetScopes).await?;
 
     scopes.iter_mut().for_each(|scope| {
         let mut formatted_description = String::new();
@@ -33,20 +53,48 @@ pub async fn get_scopes() -> Vec<ApiScope> {
         scope.description = formatted_description;
     });
 
-    scopes
+    Ok(scopes)
 }
 
 #[tokio::test]
 #[ignore]
 async fn test_get_scopes() {
-    let scopes = get_scopes().await;
+    let scopes = get_scopes(&usos_core::client::CLIENT).await;
     println!("{scopes:?}");
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ApiScope {
     #[serde(rename = "key")]
-    scope: Scope,
+    pub scope: Scope,
     #[serde(rename = "developers_description")]
-    description: String,
+    pub description: String,
+}
+
+#[tokio::test]
+async fn scope_descriptions_are_collapsed() {
+    use crate::test_utils::{json_response, mock_client, usos_method};
+    use serde_json::json;
+    use wiremock::MockServer;
+
+    let server = MockServer::start().await;
+    usos_method("apiref/scopes")
+        .respond_with(json_response(json!([
+            {
+                "key": "studies",
+                "developers_description": "  Provides access to\n    the list of studies.  "
+            },
+            { "key": "time_travel", "developers_description": "Not yet" }
+        ])))
+        .mount(&server)
+        .await;
+
+    let scopes = get_scopes(&mock_client(&server)).await.unwrap();
+
+    assert_eq!(scopes[0].scope, Scope::Studies);
+    assert_eq!(
+        scopes[0].description,
+        "Provides access to the list of studies."
+    );
+    assert_eq!(scopes[1].scope, Scope::Unknown(String::from("time_travel")));
 }
@@ -0,0 +1,431 @@
+//! Offline copy of the API reference of an installation.
+//!
+//! A snapshot is a versioned JSON file with the whole [`ApiTree`], so the reference can be searched without calling
+//! USOS API, and two snapshots (e.g. taken before and after a USOS release) can be [diffed](ApiSnapshot::diff) to
+//! spot changes that could break an application.
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+};
+use usos_core::{api::types::scopes::Scope, client::Client, errors::AppError};
+
+use super::{
+    method::{Argument, Deprecated, MethodReference},
+    tree::ApiTree,
+};
+
+/// Version of the snapshot format written by this crate.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiSnapshot {
+    /// Version of the snapshot format, see [`SNAPSHOT_VERSION`]
+    pub version: u32,
+    /// Base URL of the installation the snapshot was taken from
+    pub base_url: String,
+    pub tree: ApiTree,
+}
+
+impl ApiSnapshot {
+    /// Takes a snapshot of the installation the client points to, see [`ApiTree::crawl`].
+    pub async fn capture(client: &Client) -> usos_core::Result<Self> {
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            base_url: client.base_url().to_string(),
+            tree: ApiTree::crawl(client).await?,
+        })
+    }
+
+    /// Parses a snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the JSON is not a valid snapshot or if it was written in a newer version of the format.
+    pub fn from_json(json: &str) -> usos_core::Result<Self> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } = serde_json::from_str(json)?;
+        if version > SNAPSHOT_VERSION {
+            return Err(AppError::Unexpected(anyhow!(
+                "Snapshot format version {version} is not supported, the latest supported version is {SNAPSHOT_VERSION}"
+            )));
+        }
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> usos_core::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> usos_core::Result<Self> {
+        let json = fs::read_to_string(path).map_err(|e| AppError::Unexpected(anyhow!(e)))?;
+        Self::from_json(&json)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> usos_core::Result<()> {
+        fs::write(path, self.to_json()?).map_err(|e| AppError::Unexpected(anyhow!(e)))
+    }
+
+    /// Searches the methods by name and brief description.
+    ///
+    /// Matching ignores case and every word of the query has to be present in either of them.
+    /// Methods matching by name come first.
+    pub fn search(&self, query: &str) -> Vec<&MethodReference> {
+        let words = query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut found = self
+            .tree
+            .methods()
+            .filter_map(|method| {
+                let name = method.name.to_lowercase();
+                let brief = method.brief_description.to_lowercase();
+                let in_name = words.iter().all(|word| name.contains(word.as_str()));
+                let matches = words
+                    .iter()
+                    .all(|word| name.contains(word.as_str()) || brief.contains(word.as_str()));
+                matches.then_some((!in_name, method))
+            })
+            .collect::<Vec<_>>();
+        found.sort_by_key(|(not_in_name, _)| *not_in_name);
+        found.into_iter().map(|(_, method)| method).collect()
+    }
+
+    /// Lists the changes between this snapshot and a newer one.
+    pub fn diff(&self, newer: &ApiSnapshot) -> ApiDiff {
+        let old = methods_by_name(&self.tree);
+        let new = methods_by_name(&newer.tree);
+
+        ApiDiff {
+            added_methods: new
+                .keys()
+                .filter(|name| !old.contains_key(*name))
+                .map(|name| name.to_string())
+                .collect(),
+            removed_methods: old
+                .keys()
+                .filter(|name| !new.contains_key(*name))
+                .map(|name| name.to_string())
+                .collect(),
+            changed_methods: old
+                .iter()
+                .filter_map(|(name, old)| MethodChange::between(old, new.get(name)?))
+                .collect(),
+        }
+    }
+}
+
+fn methods_by_name(tree: &ApiTree) -> BTreeMap<&str, &MethodReference> {
+    tree.methods()
+        .map(|method| (method.name.as_str(), method))
+        .collect()
+}
+
+/// Changes between two snapshots, see [`ApiSnapshot::diff`].
+#[derive(Debug, Clone, Default)]
+pub struct ApiDiff {
+    pub added_methods: Vec<String>,
+    pub removed_methods: Vec<String>,
+    pub changed_methods: Vec<MethodChange>,
+}
+
+impl ApiDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_methods.is_empty()
+            && self.removed_methods.is_empty()
+            && self.changed_methods.is_empty()
+    }
+
+    /// Whether any of the changes can break applications using the older API.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed_methods.is_empty()
+            || self.changed_methods.iter().any(MethodChange::is_breaking)
+    }
+}
+
+/// Writes one change per line, breaking changes are marked with `!`.
+impl Display for ApiDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for name in &self.added_methods {
+            writeln!(f, "+ {name}")?;
+        }
+        for name in &self.removed_methods {
+            writeln!(f, "! - {name}")?;
+        }
+        for change in &self.changed_methods {
+            let marker = if change.is_breaking() { "!" } else { " " };
+            writeln!(f, "{marker} ~ {}", change.name)?;
+            for argument in &change.added_arguments {
+                let required = if argument.is_required {
+                    " (required)"
+                } else {
+                    ""
+                };
+                writeln!(f, "    + argument {}{required}", argument.name)?;
+            }
+            for argument in &change.removed_arguments {
+                writeln!(f, "    - argument {argument}")?;
+            }
+            for scope in &change.added_scopes {
+                writeln!(f, "    + scope {scope}")?;
+            }
+            for scope in &change.removed_scopes {
+                writeln!(f, "    - scope {scope}")?;
+            }
+            if let Some(deprecated) = &change.deprecated {
+                write!(f, "    deprecated")?;
+                if let Some(by) = &deprecated.deprecated_by {
+                    write!(f, " by {by}")?;
+                }
+                if let Some(until) = &deprecated.present_until {
+                    write!(f, ", present until {until}")?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Changes of a method present in both snapshots.
+#[derive(Debug, Clone)]
+pub struct MethodChange {
+    pub name: String,
+    pub added_arguments: Vec<Argument>,
+    /// Names of the removed arguments
+    pub removed_arguments: Vec<String>,
+    pub added_scopes: Vec<Scope>,
+    pub removed_scopes: Vec<Scope>,
+    /// Set if the method became deprecated
+    pub deprecated: Option<Deprecated>,
+}
+
+impl MethodChange {
+    /// Returns [`None`] if nothing has changed.
+    fn between(old: &MethodReference, new: &MethodReference) -> Option<Self> {
+        let old_arguments = argument_names(old);
+        let new_arguments = argument_names(new);
+        let old_scopes = HashSet::<&Scope>::from_iter(&old.auth_options.scopes);
+        let new_scopes = HashSet::<&Scope>::from_iter(&new.auth_options.scopes);
+        let scopes_difference = |a: &HashSet<&Scope>, b: &HashSet<&Scope>| {
+            let mut scopes = a
+                .difference(b)
+                .map(|&scope| scope.clone())
+                .collect::<Vec<_>>();
+            scopes.sort_by_key(Scope::to_string);
+            scopes
+        };
+
+        let change = Self {
+            name: new.name.clone(),
+            added_arguments: new
+                .arguments
+                .iter()
+                .filter(|argument| !old_arguments.contains(argument.name.as_str()))
+                .cloned()
+                .collect(),
+            removed_arguments: old
+                .arguments
+                .iter()
+                .filter(|argument| !new_arguments.contains(argument.name.as_str()))
+                .map(|argument| argument.name.clone())
+                .collect(),
+            added_scopes: scopes_difference(&new_scopes, &old_scopes),
+            removed_scopes: scopes_difference(&old_scopes, &new_scopes),
+            deprecated: new.deprecated.clone().filter(|_| old.deprecated.is_none()),
+        };
+
+        let unchanged = change.added_arguments.is_empty()
+            && change.removed_arguments.is_empty()
+            && change.added_scopes.is_empty()
+            && change.removed_scopes.is_empty()
+            && change.deprecated.is_none();
+        (!unchanged).then_some(change)
+    }
+
+    /// Removed arguments, new required arguments and new scopes break existing calls.
+    pub fn is_breaking(&self) -> bool {
+        !self.removed_arguments.is_empty()
+            || self
+                .added_arguments
+                .iter()
+                .any(|argument| argument.is_required)
+            || !self.added_scopes.is_empty()
+    }
+}
+
+fn argument_names(method: &MethodReference) -> HashSet<&str> {
+    method
+        .arguments
+        .iter()
+        .map(|argument| argument.name.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::reference::tree::tests::method;
+
+    fn argument(name: &str, is_required: bool) -> Value {
+        json!({
+            "name": name,
+            "is_required": is_required,
+            "is_deprecated": false,
+            "default_value": null,
+            "description": "",
+        })
+    }
+
+    pub(crate) fn snapshot(methods: Vec<Value>) -> ApiSnapshot {
+        serde_json::from_value(json!({
+            "version": SNAPSHOT_VERSION,
+            "base_url": "https://apps.usos.pwr.edu.pl/",
+            "tree": {
+                "modules": [{
+                    "info": {
+                        "name": "services/tt",
+                        "title": "Timetables",
+                        "brief_description": "",
+                        "description": "",
+                        "submodules": [],
+                        "methods": [],
+                        "beta": false,
+                    },
+                    "methods": methods,
+                    "submodules": [],
+                }]
+            }
+        }))
+        .unwrap()
+    }
+
+    fn with(mut method: Value, key: &str, value: Value) -> Value {
+        method[key] = value;
+        method
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let snapshot = snapshot(vec![method("services/tt/user")]);
+
+        let parsed = ApiSnapshot::from_json(&snapshot.to_json().unwrap()).unwrap();
+
+        assert_eq!(parsed.base_url, snapshot.base_url);
+        assert!(parsed.tree.method("tt/user").is_some());
+    }
+
+    #[test]
+    fn newer_format_versions_are_rejected() {
+        let mut json = serde_json::to_value(snapshot(vec![])).unwrap();
+        json["version"] = json!(SNAPSHOT_VERSION + 1);
+
+        assert!(ApiSnapshot::from_json(&json.to_string()).is_err());
+    }
+
+    #[test]
+    fn search_matches_names_before_descriptions() {
+        let snapshot = snapshot(vec![
+            with(
+                method("services/tt/room"),
+                "brief_description",
+                json!("Timetable of a room"),
+            ),
+            with(
+                method("services/tt/user"),
+                "brief_description",
+                json!("Timetable of the current user"),
+            ),
+            method("services/tt/staff"),
+        ]);
+        let names = |query| {
+            snapshot
+                .search(query)
+                .into_iter()
+                .map(|method| method.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("user"), ["services/tt/user"]);
+        assert_eq!(names("ROOM timetable"), ["services/tt/room"]);
+        assert_eq!(
+            names("tt/ timetable"),
+            ["services/tt/room", "services/tt/user"]
+        );
+        assert_eq!(names("room"), ["services/tt/room"]);
+        assert!(names(" ").is_empty());
+    }
+
+    #[test]
+    fn snapshots_are_diffed() {
+        let old = snapshot(vec![
+            with(
+                method("services/tt/user"),
+                "arguments",
+                json!([argument("start", false), argument("days", false)]),
+            ),
+            method("services/tt/room"),
+            method("services/tt/staff"),
+        ]);
+        let new = snapshot(vec![
+            with(
+                with(
+                    method("services/tt/user"),
+                    "arguments",
+                    json!([argument("start", false), argument("fields", false)]),
+                ),
+                "auth_options",
+                json!({
+                    "consumer": "required",
+                    "token": "required",
+                    "administrative_only": false,
+                    "ssl_required": false,
+                    "scopes": ["studies"],
+                }),
+            ),
+            with(
+                method("services/tt/room"),
+                "deprecated",
+                json!({ "deprecated_by": "services/tt/room2", "present_until": null }),
+            ),
+            method("services/tt/staff"),
+            method("services/tt/room2"),
+        ]);
+
+        let diff = old.diff(&new);
+
+        assert_eq!(diff.added_methods, ["services/tt/room2"]);
+        assert!(diff.removed_methods.is_empty());
+        assert_eq!(diff.changed_methods.len(), 2);
+        let room = &diff.changed_methods[0];
+        assert_eq!(room.name, "services/tt/room");
+        assert!(!room.is_breaking());
+        let user = &diff.changed_methods[1];
+        assert_eq!(user.added_arguments[0].name, "fields");
+        assert_eq!(user.removed_arguments, ["days"]);
+        assert_eq!(user.added_scopes, [Scope::Studies]);
+        assert!(diff.is_breaking());
+        assert_eq!(
+            diff.to_string(),
+            "+ services/tt/room2\n  \
+             ~ services/tt/room\n    deprecated by services/tt/room2\n\
+             ! ~ services/tt/user\n    + argument fields\n    - argument days\n    + scope studies\n"
+        );
+        assert!(new.diff(&new).is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+use futures::{future::BoxFuture, stream, FutureExt, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use usos_core::client::Client;
+
+use super::{
+    method::{get_method_info, MethodReference},
+    module::{get_module_info_by_name, get_modules, ModuleInfo},
+};
+
+/// Maximum number of `apiref/method` requests sent at once while crawling a module.
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// Reference of the whole `services` namespace of an installation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTree {
+    pub modules: Vec<ModuleNode>,
+}
+
+/// A module with the references of its methods and submodules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleNode {
+    pub info: ModuleInfo,
+    pub methods: Vec<MethodReference>,
+    pub submodules: Vec<ModuleNode>,
+}
+
+impl ApiTree {
+    /// Fetches the references of all modules, submodules and methods of the installation.
+    ///
+    /// This sends a request per module and method, so it takes a while - consider caching the result.
+    pub async fn crawl(client: &Client) -> usos_core::Result<Self> {
+        let mut modules = Vec::new();
+        for module in get_modules(client).await? {
+            modules.push(crawl_module(client, module.to_string()).await?);
+        }
+        Ok(Self { modules })
+    }
+
+    /// Iterates over the modules and all of their submodules, depth-first.
+    pub fn modules(&self) -> impl Iterator<Item = &ModuleNode> {
+        let mut stack = self.modules.iter().rev().collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            let module = stack.pop()?;
+            stack.extend(module.submodules.iter().rev());
+            Some(module)
+        })
+    }
+
+    /// Iterates over the methods of all modules, depth-first.
+    pub fn methods(&self) -> impl Iterator<Item = &MethodReference> {
+        self.modules().flat_map(|module| &module.methods)
+    }
+
+    /// Finds a method by its name, with or without the `services/` prefix.
+    pub fn method(&self, name: &str) -> Option<&MethodReference> {
+        let name = super::full_name(name);
+        self.methods().find(|method| method.name == name)
+    }
+}
+
+fn crawl_module(client: &Client, name: String) -> BoxFuture<'_, usos_core::Result<ModuleNode>> {
+    async move {
+        let info = get_module_info_by_name(client, &name).await?;
+        let methods = stream::iter(info.methods.clone())
+            .map(|method| async move { get_method_info(client, &method).await })
+            .buffered(CONCURRENT_REQUESTS)
+            .try_collect()
+            .await?;
+
+        let mut submodules = Vec::new();
+        // guards against cycles, every submodule is nested in the namespace of its parent
+        let prefix = format!("{}/", info.name);
+        for submodule in info.submodules.iter().filter(|s| s.starts_with(&prefix)) {
+            submodules.push(crawl_module(client, submodule.clone()).await?);
+        }
+
+        Ok(ModuleNode {
+            info,
+            methods,
+            submodules,
+        })
+    }
+    .boxed()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::{json, Value};
+    use wiremock::{
+        matchers::{body_string, body_string_contains},
+        MockServer,
+    };
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_client, usos_method};
+
+    pub(crate) fn method(name: &str) -> Value {
+        json!({
+            "name": name,
+            "short_name": name.rsplit('/').next().unwrap(),
+            "description": "",
+            "brief_description": format!("Brief of {name}"),
+            "ref_url": "",
+            "auth_options": {
+                "consumer": "ignored",
+                "token": "ignored",
+                "administrative_only": false,
+                "ssl_required": false,
+                "scopes": [],
+            },
+            "arguments": [],
+            "returns": "",
+            "errors": "",
+            "result_fields": [
+                { "name": null, "description": "", "is_primary": true, "is_secondary": false }
+            ],
+            "beta": false,
+            "deprecated": null,
+            "is_internal": false,
+        })
+    }
+
+    fn module(name: &str, methods: &[&str], submodules: &[&str]) -> Value {
+        json!({
+            "name": name,
+            "title": name,
+            "brief_description": "",
+            "description": "",
+            "submodules": submodules,
+            "methods": methods,
+            "beta": false,
+        })
+    }
+
+    async fn mount_module(server: &MockServer, module: Value) {
+        let name = module["name"].as_str().unwrap().replace('/', "%2F");
+        usos_method("apiref/module")
+            .and(body_string(format!("name={name}")))
+            .respond_with(json_response(module))
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_method(server: &MockServer, name: &str) {
+        usos_method("apiref/method")
+            .and(body_string_contains(format!(
+                "name={}",
+                name.replace('/', "%2F")
+            )))
+            .respond_with(json_response(method(name)))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn whole_namespace_is_crawled() {
+        let server = MockServer::start().await;
+        usos_method("apiref/method_index")
+            .respond_with(json_response(json!([
+                { "name": "services/apiref/method", "brief_description": "" },
+                { "name": "services/events/subscriptions", "brief_description": "" }
+            ])))
+            .mount(&server)
+            .await;
+        mount_module(
+            &server,
+            module("services/apiref", &["services/apiref/method"], &[]),
+        )
+        .await;
+        mount_module(
+            &server,
+            module(
+                "services/events",
+                &["services/events/subscriptions"],
+                &["services/events/push"],
+            ),
+        )
+        .await;
+        mount_module(
+            &server,
+            module(
+                "services/events/push",
+                &["services/events/push/notify"],
+                &[],
+            ),
+        )
+        .await;
+        for name in [
+            "services/apiref/method",
+            "services/events/subscriptions",
+            "services/events/push/notify",
+        ] {
+            mount_method(&server, name).await;
+        }
+
+        let tree = ApiTree::crawl(&mock_client(&server)).await.unwrap();
+
+        assert_eq!(
+            tree.modules()
+                .map(|module| module.info.name.as_str())
+                .collect::<Vec<_>>(),
+            ["services/apiref", "services/events", "services/events/push"]
+        );
+        assert_eq!(tree.methods().count(), 3);
+        assert!(tree.method("events/push/notify").is_some());
+        assert_eq!(
+            tree.method("services/apiref/method").unwrap().result_fields[0].name,
+            None
+        );
+    }
+}
@@ -1,4 +1,5 @@
 pub mod consumer;
+pub mod directory;
 pub mod installation;
 pub mod installations;
 pub mod now;
@@ -1,24 +1,39 @@
 use serde::Deserialize;
-use serde_json::Value;
-use usos_core::api::auth::AccessToken;
-use usos_core::api::params::Params;
-use usos_core::api::types::scopes::Scope;
-use usos_core::api::types::time::UsosDateTime;
-use usos_core::api::util::Selector;
-use usos_core::client::CLIENT;
-use usos_core::keys::ConsumerKey;
-
-#[derive(Deserialize, Debug)]
+use usos_core::{
+    api::{
+        auth::AccessToken,
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+        selector::UsosFields,
+        types::{scopes::Scope, time::UsosDateTime},
+    },
+    client::Client,
+};
+
+#[derive(Deserialize, Debug, UsosFields)]
 pub struct ConsumerInfo {
-    name: String,
-    url: Option<String>,
-    email: String,
-    date_registered: UsosDateTime,
-    administrative_methods: Vec<String>,
-    token_scopes: Option<Vec<Scope>>,
+    pub name: String,
+    pub url: Option<String>,
+    pub email: String,
+    pub date_registered: UsosDateTime,
+    pub administrative_methods: Vec<String>,
+    /// [`None`] if the request was not signed with an access token
+    pub token_scopes: Option<Vec<Scope>>,
+}
+
+struct GetConsumer;
+
+impl Endpoint for GetConsumer {
+    const PATH: &'static str = "apisrv/consumer";
+    const CONSUMER: Requirement = Requirement::Required;
+    type Token = token::Optional;
+    type Response = ConsumerInfo;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from(("fields", ConsumerInfo::selector())))
+    }
 }
 
-// Fields: name|url|email|date_registered|administrative_methods|token_scopes
 /// services/apisrv/consumer
 ///
 /// Consumer: required
@@ -29,8 +44,59 @@ pub struct ConsumerInfo {
 ///
 /// SSL: false
 pub async fn get_consumer_info(
-    consumer_key: &ConsumerKey,
-    token: Option<AccessToken>,
-    fields: impl Into<Selector>,
-) {
+    client: &Client,
+    token: Option<&AccessToken>,
+) -> usos_core::Result<ConsumerInfo> {
+    match token {
+        Some(token) => client.call_with_token(&GetConsumer, token).await,
+        None => client.call(&GetConsumer).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use usos_core::errors::AppError;
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_authorized_client, mock_client, usos_method};
+
+    #[tokio::test]
+    async fn consumer_info_is_fetched() {
+        let server = MockServer::start().await;
+        usos_method("apisrv/consumer")
+            .and(body_string_contains("oauth_consumer_key=key"))
+            .and(body_string_contains("fields=name%7Curl"))
+            .respond_with(json_response(json!({
+                "name": "Planer",
+                "url": null,
+                "email": "planer@example.com",
+                "date_registered": "2024-09-01 10:00:00",
+                "administrative_methods": [],
+                "token_scopes": null
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let consumer = get_consumer_info(&mock_authorized_client(&server), None)
+            .await
+            .unwrap();
+
+        assert_eq!(consumer.name, "Planer");
+        assert_eq!(consumer.url, None);
+        assert!(consumer.token_scopes.is_none());
+    }
+
+    #[tokio::test]
+    async fn consumer_key_is_required() {
+        let server = MockServer::start().await;
+
+        let error = get_consumer_info(&mock_client(&server), None)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, AppError::InvalidRequest(_)));
+    }
 }
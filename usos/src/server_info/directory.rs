@@ -0,0 +1,497 @@
+//! Directory of the USOS installations, e.g. for letting users pick their university.
+
+use futures::{future::ready, stream, StreamExt};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+use tokio::sync::OnceCell;
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+    },
+    client::Client,
+    errors::AppError,
+};
+
+use super::installations::{get_installations, InstallationListItem};
+
+/// Maximum number of installations asked for their SCHAC IDs at once.
+const CONCURRENT_REQUESTS: usize = 8;
+
+/// Cache of the directory of all installations, e.g. kept in the state of an application.
+///
+/// The directory is fetched on the first call to [`DirectoryCache::get`], later calls return it without sending any
+/// request, even if they are made with another client. Failed fetches are not cached.
+#[derive(Debug, Default)]
+pub struct DirectoryCache {
+    directory: OnceCell<InstallationDirectory>,
+}
+
+impl DirectoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the directory, fetching it with the client if it is not cached yet.
+    pub async fn get(&self, client: &Client) -> usos_core::Result<&InstallationDirectory> {
+        self.directory
+            .get_or_try_init(|| InstallationDirectory::fetch(client))
+            .await
+    }
+
+    /// Drops the cached directory, so that the next call to [`DirectoryCache::get`] fetches it again.
+    pub fn clear(&mut self) {
+        self.directory.take();
+    }
+}
+
+/// List of USOS installations with lookup helpers.
+#[derive(Debug, Default)]
+pub struct InstallationDirectory {
+    installations: Vec<InstallationListItem>,
+    /// SCHAC IDs resolved so far, by base URL
+    schac_ids: Mutex<HashMap<String, String>>,
+}
+
+impl FromIterator<InstallationListItem> for InstallationDirectory {
+    fn from_iter<T: IntoIterator<Item = InstallationListItem>>(iter: T) -> Self {
+        Self {
+            installations: Vec::from_iter(iter),
+            schac_ids: Mutex::default(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SchacId {
+    schac_id: String,
+}
+
+/// `apisrv/installation` limited to the SCHAC ID, which `apisrv/installations` does not provide.
+struct GetSchacId;
+
+impl Endpoint for GetSchacId {
+    const PATH: &'static str = "apisrv/installation";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = SchacId;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from(("fields", "schac_id")))
+    }
+}
+
+impl InstallationDirectory {
+    /// Fetches the directory from `apisrv/installations`, use [`DirectoryCache`] to fetch it once.
+    pub async fn fetch(client: &Client) -> usos_core::Result<Self> {
+        get_installations(client).await.map(Self::from_iter)
+    }
+
+    pub fn installations(&self) -> &[InstallationListItem] {
+        &self.installations
+    }
+
+    /// Searches the installations by institution name, in Polish or English.
+    ///
+    /// Matching ignores case and Polish diacritics, and every word of the query has to be present in the name, e.g.
+    /// `"politechnika wroclaw"` matches "Politechnika Wrocławska". Names starting with the query come first.
+    pub fn search(&self, query: &str) -> Vec<&InstallationListItem> {
+        let query = normalize(query);
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let mut found = self
+            .installations
+            .iter()
+            .filter_map(|installation| {
+                let names = [
+                    normalize(installation.institution_name.polish()),
+                    normalize(installation.institution_name.english()),
+                ];
+                let matches = names
+                    .iter()
+                    .any(|name| words.iter().all(|word| name.contains(word)));
+                let is_prefix = names.iter().any(|name| name.starts_with(&query));
+                matches.then_some((!is_prefix, installation))
+            })
+            .collect::<Vec<_>>();
+        // stable, so the directory order is kept within both groups
+        found.sort_by_key(|(is_not_prefix, _)| *is_not_prefix);
+        found
+            .into_iter()
+            .map(|(_, installation)| installation)
+            .collect()
+    }
+
+    /// Finds the installation hosted in the given domain.
+    ///
+    /// Accepts a bare domain (`pwr.edu.pl`) as well as an URL (`https://usosweb.pwr.edu.pl/kontroler.php`),
+    /// which makes it possible to find an installation by the address of any of the university's USOS services.
+    pub fn by_domain(&self, domain: &str) -> Option<&InstallationListItem> {
+        let domain = Url::parse(domain)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_else(|| domain.trim().trim_end_matches('/').to_lowercase());
+        let domain = domain.strip_prefix("www.").unwrap_or(&domain);
+
+        self.installations
+            .iter()
+            .filter_map(|installation| Some((host(installation)?, installation)))
+            .filter(|(host, _)| is_within(domain, institution_domain(host)))
+            // the most specific institution domain wins
+            .max_by_key(|(host, _)| institution_domain(host).len())
+            .map(|(_, installation)| installation)
+    }
+
+    /// Finds the installation of the institution with the given SCHAC home organization ID, e.g. `pwr.edu.pl`.
+    ///
+    /// `apisrv/installations` does not provide SCHAC IDs, so they are resolved with `apisrv/installation` of each
+    /// installation and cached in the directory. As SCHAC IDs are usually the domains of the institutions, the
+    /// installation found by [`InstallationDirectory::by_domain`] is asked first. Installations that cannot be reached
+    /// are skipped.
+    pub async fn by_schac_id(&self, schac_id: &str) -> Option<&InstallationListItem> {
+        let candidate = self.by_domain(schac_id);
+        if let Some(candidate) = candidate {
+            if self.schac_id(candidate).await.as_deref() == Some(schac_id) {
+                return Some(candidate);
+            }
+        }
+
+        let others = self.installations.iter().filter(move |installation| {
+            !candidate.is_some_and(|candidate| std::ptr::eq(candidate, *installation))
+        });
+        // stops at the first match, so the installations after it are not asked
+        let found = stream::iter(others)
+            .map(|installation| async move { (self.schac_id(installation).await, installation) })
+            .buffered(CONCURRENT_REQUESTS)
+            .filter(|(id, _)| ready(id.as_deref() == Some(schac_id)))
+            .map(|(_, installation)| installation);
+        std::pin::pin!(found).next().await
+    }
+
+    /// SCHAC ID of the installation, fetched from `apisrv/installation` on the first call and cached in the directory.
+    ///
+    /// [`None`] if the installation cannot be reached, in which case nothing is cached.
+    pub async fn schac_id(&self, installation: &InstallationListItem) -> Option<String> {
+        let cached = self
+            .schac_ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&installation.base_url)
+            .cloned();
+        if cached.is_some() {
+            return cached;
+        }
+
+        let schac_id = installation
+            .client()
+            .ok()?
+            .call(&GetSchacId)
+            .await
+            .ok()?
+            .schac_id;
+        self.schac_ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(installation.base_url.clone(), schac_id.clone());
+        Some(schac_id)
+    }
+}
+
+impl InstallationListItem {
+    /// Creates a client for this installation.
+    ///
+    /// Consumer keys are issued by each installation separately, so the client is not authorized.
+    pub fn client(&self) -> usos_core::Result<Client> {
+        let base_url = Url::parse(&self.base_url).map_err(|e| {
+            AppError::InvalidRequest(format!("Invalid base URL '{}': {e}", self.base_url))
+        })?;
+        Ok(Client::new(base_url))
+    }
+}
+
+fn host(installation: &InstallationListItem) -> Option<String> {
+    Url::parse(&installation.base_url)
+        .ok()?
+        .host_str()
+        .map(str::to_lowercase)
+}
+
+/// Whether `host` is `domain` or one of its subdomains.
+fn is_within(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.'))
+}
+
+/// Strips the USOS-specific subdomains, e.g. `apps.usos.pwr.edu.pl` becomes `pwr.edu.pl`.
+fn institution_domain(host: &str) -> &str {
+    ["apps.usos.", "usosapps.", "apps.", "usos."]
+        .iter()
+        .find_map(|prefix| host.strip_prefix(prefix))
+        .unwrap_or(host)
+}
+
+/// Lowercases the text and replaces Polish letters with their ASCII counterparts.
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ó' => 'o',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate, Times,
+    };
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_client, usos_method};
+
+    fn installations() -> serde_json::Value {
+        json!([
+            {
+                "base_url": "https://apps.usos.pwr.edu.pl/",
+                "version": "7.1.0.0-1",
+                "institution_name": { "pl": "Politechnika Wrocławska", "en": "Wrocław University of Science and Technology" },
+                "contact_emails": []
+            },
+            {
+                "base_url": "https://usosapps.uw.edu.pl/",
+                "version": "7.1.0.0-1",
+                "institution_name": { "pl": "Uniwersytet Warszawski", "en": "University of Warsaw" },
+                "contact_emails": []
+            },
+            {
+                "base_url": "https://usosapps.uwr.edu.pl/",
+                "version": null,
+                "institution_name": { "pl": "Uniwersytet Wrocławski", "en": "University of Wrocław" },
+                "contact_emails": []
+            }
+        ])
+    }
+
+    fn directory() -> InstallationDirectory {
+        serde_json::from_value::<Vec<InstallationListItem>>(installations())
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    fn base_urls(found: Vec<&InstallationListItem>) -> Vec<&str> {
+        found
+            .into_iter()
+            .map(|installation| installation.base_url.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn search_ignores_case_and_diacritics() {
+        let directory = directory();
+
+        assert_eq!(
+            base_urls(directory.search("WROCLAW")),
+            [
+                "https://apps.usos.pwr.edu.pl/",
+                "https://usosapps.uwr.edu.pl/"
+            ]
+        );
+        assert_eq!(
+            base_urls(directory.search("uniwersytet wrocławski")),
+            ["https://usosapps.uwr.edu.pl/"]
+        );
+        assert!(directory.search("  ").is_empty());
+    }
+
+    #[test]
+    fn search_matches_english_names_and_ranks_prefixes_first() {
+        let directory = directory();
+
+        assert_eq!(
+            base_urls(directory.search("university warsaw")),
+            ["https://usosapps.uw.edu.pl/"]
+        );
+        assert_eq!(
+            base_urls(directory.search("university")),
+            [
+                "https://usosapps.uw.edu.pl/",
+                "https://usosapps.uwr.edu.pl/",
+                "https://apps.usos.pwr.edu.pl/"
+            ]
+        );
+    }
+
+    #[test]
+    fn installations_are_found_by_domain() {
+        let directory = directory();
+        let found = |domain| directory.by_domain(domain).map(|i| i.base_url.as_str());
+
+        assert_eq!(found("pwr.edu.pl"), Some("https://apps.usos.pwr.edu.pl/"));
+        assert_eq!(
+            found("https://usosweb.uw.edu.pl/kontroler.php"),
+            Some("https://usosapps.uw.edu.pl/")
+        );
+        assert_eq!(
+            found("www.uwr.edu.pl"),
+            Some("https://usosapps.uwr.edu.pl/")
+        );
+        assert_eq!(found("edu.pl"), None);
+        assert_eq!(found("agh.edu.pl"), None);
+    }
+
+    /// Directory of installations served by the mock server under `/{name}/`.
+    fn mock_directory(server: &MockServer, names: &[&str]) -> InstallationDirectory {
+        names
+            .iter()
+            .map(|name| {
+                serde_json::from_value(json!({
+                    "base_url": format!("{}/{name}/", server.uri()),
+                    "version": null,
+                    "institution_name": { "pl": name, "en": null },
+                    "contact_emails": []
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    async fn mount_schac_id(
+        server: &MockServer,
+        name: &str,
+        response: ResponseTemplate,
+        expected_requests: impl Into<Times>,
+    ) {
+        Mock::given(method("POST"))
+            .and(path(format!("/{name}/services/apisrv/installation")))
+            .respond_with(response)
+            .expect(expected_requests)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn installations_are_found_by_schac_id() {
+        let server = MockServer::start().await;
+        mount_schac_id(
+            &server,
+            "pwr",
+            json_response(json!({ "schac_id": "pwr.edu.pl" })),
+            1,
+        )
+        .await;
+        // failures are not cached, the second lookup may ask again while looking past "pwr"
+        mount_schac_id(&server, "down", ResponseTemplate::new(500), 1..=2).await;
+        mount_schac_id(
+            &server,
+            "uw",
+            json_response(json!({ "schac_id": "uw.edu.pl" })),
+            1,
+        )
+        .await;
+        let directory = mock_directory(&server, &["pwr", "down", "uw"]);
+
+        let found = directory.by_schac_id("uw.edu.pl").await;
+        // resolved IDs are cached, so no more requests are sent
+        let found_again = directory.by_schac_id("pwr.edu.pl").await;
+
+        assert!(found.unwrap().base_url.ends_with("/uw/"));
+        assert!(found_again.unwrap().base_url.ends_with("/pwr/"));
+        assert_eq!(
+            directory
+                .schac_id(&directory.installations()[0])
+                .await
+                .as_deref(),
+            Some("pwr.edu.pl")
+        );
+    }
+
+    #[tokio::test]
+    async fn schac_id_lookup_stops_at_first_match() {
+        let server = MockServer::start().await;
+        let names = (0..CONCURRENT_REQUESTS + 2)
+            .map(|i| format!("installation{i}"))
+            .collect::<Vec<_>>();
+        mount_schac_id(
+            &server,
+            &names[0],
+            json_response(json!({ "schac_id": "pwr.edu.pl" })),
+            1,
+        )
+        .await;
+        // the rest of the first batch may already be in flight when the match is found
+        for name in &names[1..CONCURRENT_REQUESTS] {
+            mount_schac_id(
+                &server,
+                name,
+                json_response(json!({ "schac_id": "other.edu.pl" })),
+                0..=1,
+            )
+            .await;
+        }
+        for name in &names[CONCURRENT_REQUESTS..] {
+            mount_schac_id(
+                &server,
+                name,
+                json_response(json!({ "schac_id": "other.edu.pl" })),
+                0,
+            )
+            .await;
+        }
+        let directory = mock_directory(
+            &server,
+            &names.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+
+        let found = directory.by_schac_id("pwr.edu.pl").await;
+
+        assert!(found.unwrap().base_url.ends_with("/installation0/"));
+    }
+
+    #[test]
+    fn client_points_to_the_installation() {
+        let directory = directory();
+        let client = directory.by_domain("pwr.edu.pl").unwrap().client().unwrap();
+
+        assert_eq!(client.base_url().as_str(), "https://apps.usos.pwr.edu.pl/");
+    }
+
+    #[tokio::test]
+    async fn directory_is_cached_until_cleared() {
+        let server = MockServer::start().await;
+        usos_method("apisrv/installations")
+            .respond_with(json_response(installations()))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let client = mock_client(&server);
+        let mut cache = DirectoryCache::new();
+
+        let first = cache.get(&client).await.unwrap() as *const _;
+        let second = cache.get(&client).await.unwrap();
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(second.installations().len(), 3);
+
+        cache.clear();
+        assert_eq!(cache.get(&client).await.unwrap().installations().len(), 3);
+    }
+}
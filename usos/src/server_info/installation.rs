@@ -1,29 +1,45 @@
 use serde::Deserialize;
-use serde_json::Value;
 use usos_core::{
-    api::util::Selector,
-    api::{params::Params, types::language::LanguageDictionary},
-    client::CLIENT,
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        params::Params,
+        selector::UsosFields,
+        types::language::LanguageDictionary,
+    },
+    client::Client,
 };
 
 use crate::faculties::faculty::Faculty;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, UsosFields)]
 pub struct Installation {
-    base_url: String,
-    version: String,
+    pub base_url: String,
+    pub version: String,
     #[serde(rename = "machine_version")]
-    machine_readable_version: String,
-    usos_schema_version: String,
-    institution_name: LanguageDictionary,
+    pub machine_readable_version: String,
+    pub usos_schema_version: String,
+    pub institution_name: LanguageDictionary,
     #[serde(rename = "institution")]
-    primary_faculty: Faculty,
-    contact_emails: Vec<String>,
-    schac_id: String,
-    mcards_support: bool,
+    #[usos(nested)]
+    pub primary_faculty: Faculty,
+    pub contact_emails: Vec<String>,
+    pub schac_id: String,
+    pub mcards_support: bool,
+}
+
+struct GetInstallation;
+
+impl Endpoint for GetInstallation {
+    const PATH: &'static str = "apisrv/installation";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = Installation;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        Ok(Params::from(("fields", Installation::selector())))
+    }
 }
 
-// Fields: base_url|version|machine_version|usos_schema_version|institution_name|institution[id|name|profile_url|homepage_url|phone_numbers|phone_numbers2|postal_address|email|is_public|static_map_urls]|contact_emails|schac_id|mcards_support
 /// services/apisrv/installation
 ///
 /// Consumer: ignored
@@ -33,4 +49,61 @@ pub struct Installation {
 /// Scopes: []
 ///
 /// SSL: false
-pub async fn get_installation_info(fields: Option<Selector>) {}
+pub async fn get_installation_info(client: &Client) -> usos_core::Result<Installation> {
+    client.call(&GetInstallation).await
+}
+
+#[test]
+fn installation_selector_matches_struct() {
+    assert_eq!(
+        Installation::selector().to_string(),
+        "base_url|version|machine_version|usos_schema_version|institution_name|institution[id|name|profile_url|homepage_url|phone_numbers|phone_numbers2|postal_address|email|is_public|stats[course_count|programme_count|staff_count|subfaculty_count|public_subfaculty_count]|path[id|name]|static_map_urls]|contact_emails|schac_id|mcards_support"
+    );
+}
+
+#[tokio::test]
+async fn installation_info_is_fetched() {
+    use crate::test_utils::{json_response, mock_client, usos_method};
+    use serde_json::json;
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    let server = MockServer::start().await;
+    usos_method("apisrv/installation")
+        .and(body_string_contains("fields=base_url%7Cversion"))
+        .respond_with(json_response(json!({
+            "base_url": "https://apps.usos.pwr.edu.pl/",
+            "version": "7.1.0.0-1",
+            "machine_version": "7.1.0.0-1",
+            "usos_schema_version": "7.1.0.0-1",
+            "institution_name": { "pl": "Politechnika Wrocławska", "en": "Wrocław University of Science and Technology" },
+            "institution": {
+                "id": "00000000",
+                "name": { "pl": "Politechnika Wrocławska", "en": "Wrocław University of Science and Technology" },
+                "profile_url": "https://web.usos.pwr.edu.pl/kontroler.php?_action=katalog2/jednostki/pokazJednostke&kod=00000000",
+                "homepage_url": "https://pwr.edu.pl",
+                "phone_numbers": ["+48 71 320 22 11"],
+                "phone_numbers2": [{ "comment": null, "number": "+48 71 320 22 11", "type": "phone" }],
+                "postal_address": "Wybrzeże Wyspiańskiego 27, 50-370 Wrocław",
+                "email": null,
+                "is_public": true,
+                "stats": { "course_count": null, "programme_count": null, "staff_count": null, "subfaculty_count": 12, "public_subfaculty_count": 12 },
+                "path": [],
+                "static_map_urls": {}
+            },
+            "contact_emails": ["usos@pwr.edu.pl"],
+            "schac_id": "pwr.edu.pl",
+            "mcards_support": true
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let installation = get_installation_info(&mock_client(&server)).await.unwrap();
+
+    assert_eq!(installation.schac_id, "pwr.edu.pl");
+    assert_eq!(
+        installation.institution_name.polish(),
+        "Politechnika Wrocławska"
+    );
+    assert!(installation.mcards_support);
+}
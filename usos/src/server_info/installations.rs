@@ -1,13 +1,28 @@
 use serde::Deserialize;
-
-use usos_core::{api::types::language::LanguageDictionary, client::CLIENT};
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        types::language::LanguageDictionary,
+    },
+    client::Client,
+};
 
 #[derive(Deserialize, Debug)]
 pub struct InstallationListItem {
-    base_url: String,
-    contact_emails: Vec<String>,
-    institution_name: LanguageDictionary,
-    version: Option<String>,
+    pub base_url: String,
+    pub contact_emails: Vec<String>,
+    pub institution_name: LanguageDictionary,
+    /// [`None`] if the installation does not report its version
+    pub version: Option<String>,
+}
+
+struct GetInstallations;
+
+impl Endpoint for GetInstallations {
+    const PATH: &'static str = "apisrv/installations";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = Vec<InstallationListItem>;
 }
 
 /// services/apisrv/installations
@@ -19,4 +34,43 @@ pub struct InstallationListItem {
 /// Scopes: []
 ///
 /// SSL: false
-pub async fn get_installations() {}
+pub async fn get_installations(client: &Client) -> usos_core::Result<Vec<InstallationListItem>> {
+    client.call(&GetInstallations).await
+}
+
+#[tokio::test]
+async fn installations_are_fetched() {
+    use crate::test_utils::{json_response, mock_client, usos_method};
+    use serde_json::json;
+    use wiremock::MockServer;
+
+    let server = MockServer::start().await;
+    usos_method("apisrv/installations")
+        .respond_with(json_response(json!([
+            {
+                "base_url": "https://apps.usos.pwr.edu.pl/",
+                "version": "7.1.0.0-1",
+                "institution_name": { "pl": "Politechnika Wrocławska", "en": "Wrocław University of Science and Technology" },
+                "contact_emails": ["usos@pwr.edu.pl"]
+            },
+            {
+                "base_url": "https://usosapps.uw.edu.pl/",
+                "version": null,
+                "institution_name": { "pl": "Uniwersytet Warszawski", "en": "University of Warsaw" },
+                "contact_emails": []
+            }
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let installations = get_installations(&mock_client(&server)).await.unwrap();
+
+    assert_eq!(installations.len(), 2);
+    assert_eq!(installations[0].version.as_deref(), Some("7.1.0.0-1"));
+    assert_eq!(installations[1].version, None);
+    assert_eq!(
+        installations[1].institution_name.english(),
+        "University of Warsaw"
+    );
+}
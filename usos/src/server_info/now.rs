@@ -1,7 +1,19 @@
-use anyhow::Context;
-use serde::Deserialize;
-use serde_json::Value;
-use usos_core::{api::types::time::UsosPreciseDateTime, client::CLIENT};
+use usos_core::{
+    api::{
+        endpoint::{token, Endpoint, Requirement},
+        types::time::UsosPreciseDateTime,
+    },
+    client::Client,
+};
+
+struct GetNow;
+
+impl Endpoint for GetNow {
+    const PATH: &'static str = "apisrv/now";
+    const CONSUMER: Requirement = Requirement::Ignored;
+    type Token = token::Ignored;
+    type Response = UsosPreciseDateTime;
+}
 
 /// services/apisrv/now
 ///
@@ -12,4 +24,25 @@ use usos_core::{api::types::time::UsosPreciseDateTime, client::CLIENT};
 /// Scopes: []
 ///
 /// SSL: false
-pub async fn get_usos_server_time() {}
+pub async fn get_usos_server_time(client: &Client) -> usos_core::Result<UsosPreciseDateTime> {
+    client.call(&GetNow).await
+}
+
+#[tokio::test]
+async fn server_time_is_fetched() {
+    use crate::test_utils::{json_response, mock_client, usos_method};
+    use serde_json::json;
+    use time::macros::datetime;
+    use wiremock::MockServer;
+
+    let server = MockServer::start().await;
+    usos_method("apisrv/now")
+        .respond_with(json_response(json!("2024-10-01 12:34:56.789012")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let now = get_usos_server_time(&mock_client(&server)).await.unwrap();
+
+    assert_eq!(now.0, datetime!(2024-10-01 12:34:56.789012));
+}
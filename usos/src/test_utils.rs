@@ -0,0 +1,30 @@
+use reqwest::Url;
+use serde_json::Value;
+use usos_core::{client::Client, keys::ConsumerKey};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockBuilder, MockServer, ResponseTemplate,
+};
+
+/// Client that sends its requests to the mock server.
+pub(crate) fn mock_client(server: &MockServer) -> Client {
+    Client::new(Url::parse(&server.uri()).unwrap())
+}
+
+/// Same as [`mock_client`], but signs the requests with a dummy consumer key.
+pub(crate) fn mock_authorized_client(server: &MockServer) -> Client {
+    mock_client(server).authorized_from_key(ConsumerKey::new(
+        "key".into(),
+        "secret".to_string().into(),
+        None,
+    ))
+}
+
+/// Matches a request to the given method, e.g. `apisrv/installation`.
+pub(crate) fn usos_method(name: &str) -> MockBuilder {
+    Mock::given(method("POST")).and(path(format!("/services/{name}")))
+}
+
+pub(crate) fn json_response(body: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(body)
+}
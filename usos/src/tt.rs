@@ -0,0 +1,649 @@
+//! Timetables of users, groups, rooms and course editions.
+//!
+//! USOS API returns at most 7 days of a timetable per request, so the functions of this module accept arbitrary date
+//! ranges, split them into 7-day chunks, fetch the chunks concurrently and merge the activities in order.
+
+pub mod analysis;
+
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fmt::{self, Display, Formatter},
+    future::Future,
+    str::FromStr,
+};
+use time::{Date, Duration};
+use usos_core::{
+    api::{
+        auth::AccessToken,
+        endpoint::{Endpoint, NotRequired, Requirement, TokenRequirement},
+        params::{ParamString, Params},
+        selector::UsosFields,
+        types::{
+            ids::{BuildingId, CourseId, CourseUnitId, RoomId, TermId, UserId},
+            language::LanguageDictionary,
+            time::{UsosDate, UsosDateTime},
+        },
+    },
+    client::Client,
+};
+
+/// Maximum number of days USOS API returns per request.
+pub const MAX_DAYS_PER_REQUEST: u8 = 7;
+/// Maximum number of chunks fetched at once.
+const CONCURRENT_REQUESTS: usize = 4;
+
+/// Type of a timetable activity.
+///
+/// Types not known to this crate are represented by [`ActivityType::Unknown`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ActivityType {
+    /// Classes of a class group
+    ClassGroup,
+    /// Classes of a class group, with the details of the course unit
+    ClassGroup2,
+    Meeting,
+    Exam,
+    /// Type not known to this crate, with its code.
+    Unknown(String),
+}
+
+impl Display for ActivityType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ActivityType::ClassGroup => write!(f, "classgroup"),
+            ActivityType::ClassGroup2 => write!(f, "classgroup2"),
+            ActivityType::Meeting => write!(f, "meeting"),
+            ActivityType::Exam => write!(f, "exam"),
+            ActivityType::Unknown(code) => write!(f, "{code}"),
+        }
+    }
+}
+
+impl From<&str> for ActivityType {
+    fn from(s: &str) -> Self {
+        match s {
+            "classgroup" => Self::ClassGroup,
+            "classgroup2" => Self::ClassGroup2,
+            "meeting" => Self::Meeting,
+            "exam" => Self::Exam,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ActivityType {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<ActivityType> for String {
+    fn from(activity_type: ActivityType) -> Self {
+        activity_type.to_string()
+    }
+}
+
+impl FromStr for ActivityType {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.into())
+    }
+}
+
+/// A single activity of a timetable.
+///
+/// Fields that do not apply to the type of the activity (e.g. the course of a meeting) are [`None`].
+#[derive(Debug, Clone, PartialEq, Deserialize, UsosFields)]
+pub struct Activity {
+    #[serde(rename = "type")]
+    pub activity_type: ActivityType,
+    pub start_time: UsosDateTime,
+    pub end_time: UsosDateTime,
+    pub name: LanguageDictionary,
+    pub course_id: Option<CourseId>,
+    pub course_name: Option<LanguageDictionary>,
+    /// Type of the classes, e.g. lecture
+    pub classtype_name: Option<LanguageDictionary>,
+    pub unit_id: Option<CourseUnitId>,
+    pub group_number: Option<u32>,
+    pub building_id: Option<BuildingId>,
+    pub building_name: Option<LanguageDictionary>,
+    pub room_id: Option<RoomId>,
+    pub room_number: Option<String>,
+    pub lecturer_ids: Option<Vec<UserId>>,
+}
+
+/// Whose timetable is fetched, implemented by the types of the [`source`] module.
+///
+/// Sources are turned into [`Endpoint`]s for single chunks of the date range, so the access token requirement of each
+/// method is checked by [`Client::call`] and [`Client::call_with_token`].
+pub trait TimetableSource {
+    /// Path of the method, e.g. `tt/user`.
+    const PATH: &'static str;
+    const CONSUMER: Requirement;
+    /// One of the markers of [`token`](usos_core::api::endpoint::token).
+    type Token: TokenRequirement;
+
+    /// Parameters identifying the timetable, without the date range and the fields.
+    fn params(&self) -> Params;
+}
+
+/// Sources of timetables, one for each `tt` method.
+pub mod source {
+    use usos_core::api::{
+        endpoint::{token, Requirement},
+        params::{ParamString, Params},
+        types::ids::{CourseId, CourseUnitId, RoomId, TermId, UserId},
+    };
+
+    use super::TimetableSource;
+
+    /// tt/user, the activities of the user identified by the access token
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct User;
+
+    /// tt/student, the classes the user identified by the access token attends as a student
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Student;
+
+    /// tt/staff, the classes taught by the staff member
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Staff(pub UserId);
+
+    /// tt/classgroup
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct ClassGroup {
+        pub unit_id: CourseUnitId,
+        pub group_number: u32,
+    }
+
+    /// tt/room
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Room(pub RoomId);
+
+    /// tt/course_edition
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CourseEdition {
+        pub course_id: CourseId,
+        pub term_id: TermId,
+    }
+
+    impl TimetableSource for User {
+        const PATH: &'static str = "tt/user";
+        const CONSUMER: Requirement = Requirement::Required;
+        type Token = token::Required;
+
+        fn params(&self) -> Params {
+            Params::from(())
+        }
+    }
+
+    impl TimetableSource for Student {
+        const PATH: &'static str = "tt/student";
+        const CONSUMER: Requirement = Requirement::Required;
+        type Token = token::Required;
+
+        fn params(&self) -> Params {
+            Params::from(())
+        }
+    }
+
+    impl TimetableSource for Staff {
+        const PATH: &'static str = "tt/staff";
+        const CONSUMER: Requirement = Requirement::Optional;
+        type Token = token::Optional;
+
+        fn params(&self) -> Params {
+            Params::from(("user_id", &self.0))
+        }
+    }
+
+    impl TimetableSource for ClassGroup {
+        const PATH: &'static str = "tt/classgroup";
+        const CONSUMER: Requirement = Requirement::Optional;
+        type Token = token::Optional;
+
+        fn params(&self) -> Params {
+            Params::from([
+                ("unit_id", ParamString::from(&self.unit_id)),
+                ("group_number", self.group_number.into()),
+            ])
+        }
+    }
+
+    impl TimetableSource for Room {
+        const PATH: &'static str = "tt/room";
+        const CONSUMER: Requirement = Requirement::Optional;
+        type Token = token::Optional;
+
+        fn params(&self) -> Params {
+            Params::from(("room_id", &self.0))
+        }
+    }
+
+    impl TimetableSource for CourseEdition {
+        const PATH: &'static str = "tt/course_edition";
+        const CONSUMER: Requirement = Requirement::Optional;
+        type Token = token::Optional;
+
+        fn params(&self) -> Params {
+            Params::from([
+                ("course_id", ParamString::from(&self.course_id)),
+                ("term_id", (&self.term_id).into()),
+            ])
+        }
+    }
+}
+
+/// A chunk of at most [`MAX_DAYS_PER_REQUEST`] days of a timetable.
+struct Chunk<'a, S> {
+    source: &'a S,
+    start: Date,
+    days: u8,
+}
+
+impl<S: TimetableSource> Endpoint for Chunk<'_, S> {
+    const PATH: &'static str = S::PATH;
+    const CONSUMER: Requirement = S::CONSUMER;
+    type Token = S::Token;
+    type Response = Vec<Activity>;
+
+    fn params(&self) -> usos_core::Result<Params> {
+        let mut params = self.source.params();
+        params.extend(
+            Params::from([
+                ("start", ParamString::from(UsosDate(self.start))),
+                ("days", self.days.into()),
+                ("fields", Activity::selector().into()),
+            ])
+            .0,
+        );
+        Ok(params)
+    }
+}
+
+/// Splits the range (both ends included) into chunks of at most [`MAX_DAYS_PER_REQUEST`] days.
+///
+/// Returns the start and the number of days of each chunk, in order. The range is empty if `end` precedes `start`.
+pub fn split_range(start: Date, end: Date) -> Vec<(Date, u8)> {
+    let mut chunks = Vec::new();
+    let mut chunk_start = start;
+    while chunk_start <= end {
+        let remaining = (end - chunk_start).whole_days() + 1;
+        let days = remaining.min(MAX_DAYS_PER_REQUEST as i64) as u8;
+        chunks.push((chunk_start, days));
+        chunk_start += Duration::days(days as i64);
+    }
+    chunks
+}
+
+/// Fetches the chunks of the date range concurrently and merges them in order.
+async fn fetch_range<'a, S, F, Fut>(
+    source: &'a S,
+    start: Date,
+    end: Date,
+    call: F,
+) -> usos_core::Result<Vec<Activity>>
+where
+    F: Fn(Chunk<'a, S>) -> Fut,
+    Fut: Future<Output = usos_core::Result<Vec<Activity>>>,
+{
+    let chunks: Vec<Vec<Activity>> = stream::iter(split_range(start, end))
+        .map(|(start, days)| {
+            call(Chunk {
+                source,
+                start,
+                days,
+            })
+        })
+        .buffered(CONCURRENT_REQUESTS)
+        .try_collect()
+        .await?;
+
+    let mut activities = chunks.into_iter().flatten().collect::<Vec<_>>();
+    // chunks are already in order, but activities within a chunk are not guaranteed to be
+    activities.sort_by_key(|activity| (activity.start_time, activity.end_time));
+    Ok(activities)
+}
+
+/// Fetches the timetable for the date range (both ends included), see the [module documentation](self).
+///
+/// Only sources that do not require an access token are accepted, use [`get_timetable_with_token`] for the others.
+/// Activities are sorted by their start.
+///
+/// ```compile_fail,E0277
+/// # use time::macros::date;
+/// # use usos::tt::{get_timetable, source};
+/// # async fn run(client: &usos_core::client::Client) {
+/// let timetable = get_timetable(client, &source::User, date!(2024-10-01), date!(2024-10-31)).await;
+/// # }
+/// ```
+pub async fn get_timetable<S>(
+    client: &Client,
+    source: &S,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>>
+where
+    S: TimetableSource,
+    S::Token: NotRequired,
+{
+    fetch_range(source, start, end, |chunk| async move {
+        client.call(&chunk).await
+    })
+    .await
+}
+
+/// Same as [`get_timetable`], but on behalf of the user identified by the access token.
+pub async fn get_timetable_with_token<S: TimetableSource>(
+    client: &Client,
+    token: &AccessToken,
+    source: &S,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    fetch_range(source, start, end, |chunk| async move {
+        client.call_with_token(&chunk, token).await
+    })
+    .await
+}
+
+/// tt/user
+///
+/// Consumer: required
+///
+/// Token: required
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_user_timetable(
+    client: &Client,
+    token: &AccessToken,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    get_timetable_with_token(client, token, &source::User, start, end).await
+}
+
+/// tt/student
+///
+/// Consumer: required
+///
+/// Token: required
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_student_timetable(
+    client: &Client,
+    token: &AccessToken,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    get_timetable_with_token(client, token, &source::Student, start, end).await
+}
+
+/// tt/staff
+///
+/// Consumer: optional
+///
+/// Token: optional
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_staff_timetable(
+    client: &Client,
+    user_id: &UserId,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    get_timetable(client, &source::Staff(user_id.clone()), start, end).await
+}
+
+/// tt/classgroup
+///
+/// Consumer: optional
+///
+/// Token: optional
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_classgroup_timetable(
+    client: &Client,
+    unit_id: &CourseUnitId,
+    group_number: u32,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    let source = source::ClassGroup {
+        unit_id: unit_id.clone(),
+        group_number,
+    };
+    get_timetable(client, &source, start, end).await
+}
+
+/// tt/room
+///
+/// Consumer: optional
+///
+/// Token: optional
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_room_timetable(
+    client: &Client,
+    room_id: &RoomId,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    get_timetable(client, &source::Room(room_id.clone()), start, end).await
+}
+
+/// tt/course_edition
+///
+/// Consumer: optional
+///
+/// Token: optional
+///
+/// Scopes: n/a
+///
+/// SSL: not required
+pub async fn get_course_edition_timetable(
+    client: &Client,
+    course_id: &CourseId,
+    term_id: &TermId,
+    start: Date,
+    end: Date,
+) -> usos_core::Result<Vec<Activity>> {
+    let source = source::CourseEdition {
+        course_id: course_id.clone(),
+        term_id: term_id.clone(),
+    };
+    get_timetable(client, &source, start, end).await
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::{json, Value};
+    use std::time::Duration as StdDuration;
+    use time::macros::{date, datetime};
+    use wiremock::{matchers::body_string_contains, MockServer};
+
+    use super::*;
+    use crate::test_utils::{json_response, mock_authorized_client, mock_client, usos_method};
+
+    /// `tt/*` response item of a lecture in room 0.31 of building C-13.
+    pub(crate) fn lecture(unit_id: u64, start: &str, end: &str) -> Value {
+        json!({
+            "type": "classgroup",
+            "start_time": start,
+            "end_time": end,
+            "name": { "pl": "Analiza matematyczna - Wykład", "en": "Mathematical analysis - Lecture" },
+            "course_id": "MAT001",
+            "course_name": { "pl": "Analiza matematyczna", "en": "Mathematical analysis" },
+            "classtype_name": { "pl": "Wykład", "en": "Lecture" },
+            "unit_id": unit_id,
+            "group_number": 1,
+            "building_id": "C-13",
+            "building_name": { "pl": "Budynek C-13", "en": "Building C-13" },
+            "room_id": 1234,
+            "room_number": "0.31",
+            "lecturer_ids": [42]
+        })
+    }
+
+    #[test]
+    fn ranges_are_split_into_weeks() {
+        assert_eq!(
+            split_range(date!(2024 - 10 - 01), date!(2024 - 10 - 16)),
+            [
+                (date!(2024 - 10 - 01), 7),
+                (date!(2024 - 10 - 08), 7),
+                (date!(2024 - 10 - 15), 2)
+            ]
+        );
+        assert_eq!(
+            split_range(date!(2024 - 10 - 01), date!(2024 - 10 - 01)),
+            [(date!(2024 - 10 - 01), 1)]
+        );
+        assert!(split_range(date!(2024 - 10 - 02), date!(2024 - 10 - 01)).is_empty());
+    }
+
+    #[test]
+    fn activities_are_decoded() {
+        let meeting = Activity::deserialize(json!({
+            "type": "meeting",
+            "start_time": "2024-10-01 18:00:00",
+            "end_time": "2024-10-01 19:30:00",
+            "name": { "pl": "Zebranie samorządu", "en": null },
+            "course_id": null,
+            "course_name": null,
+            "classtype_name": null,
+            "unit_id": null,
+            "group_number": null,
+            "building_id": null,
+            "building_name": null,
+            "room_id": null,
+            "room_number": null,
+            "lecturer_ids": null
+        }))
+        .unwrap();
+        let lecture =
+            Activity::deserialize(lecture(98765, "2024-10-01 07:30:00", "2024-10-01 09:00:00"))
+                .unwrap();
+
+        assert_eq!(meeting.activity_type, ActivityType::Meeting);
+        assert_eq!(meeting.course_id, None);
+        assert_eq!(lecture.start_time.0, datetime!(2024-10-01 07:30));
+        assert_eq!(lecture.unit_id, Some(CourseUnitId::new("98765")));
+        assert_eq!(lecture.room_id, Some(RoomId::new("1234")));
+        assert_eq!(lecture.lecturer_ids, Some(vec![UserId::new("42")]));
+    }
+
+    #[tokio::test]
+    async fn range_is_fetched_in_chunks_and_merged_in_order() {
+        let server = MockServer::start().await;
+        // the first chunk arrives last
+        usos_method("tt/room")
+            .and(body_string_contains("start=2024-10-01"))
+            .and(body_string_contains("days=7"))
+            .and(body_string_contains("room_id=1234"))
+            .respond_with(
+                json_response(json!([
+                    lecture(2, "2024-10-03 07:30:00", "2024-10-03 09:00:00"),
+                    lecture(1, "2024-10-01 07:30:00", "2024-10-01 09:00:00")
+                ]))
+                .set_delay(StdDuration::from_millis(200)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        usos_method("tt/room")
+            .and(body_string_contains("start=2024-10-08"))
+            .and(body_string_contains("days=3"))
+            .respond_with(json_response(json!([lecture(
+                3,
+                "2024-10-08 07:30:00",
+                "2024-10-08 09:00:00"
+            )])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let activities = get_room_timetable(
+            &mock_client(&server),
+            &RoomId::new("1234"),
+            date!(2024 - 10 - 01),
+            date!(2024 - 10 - 10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            activities
+                .iter()
+                .map(|activity| activity.unit_id.clone().unwrap())
+                .collect::<Vec<_>>(),
+            [
+                CourseUnitId::new("1"),
+                CourseUnitId::new("2"),
+                CourseUnitId::new("3")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn sources_send_their_parameters() {
+        let server = MockServer::start().await;
+        let expectations = [
+            ("tt/user", "oauth_token=token"),
+            ("tt/student", "oauth_token=token"),
+            ("tt/staff", "user_id=42"),
+            ("tt/classgroup", "group_number=2"),
+            ("tt/course_edition", "term_id=2024%2F25-Z"),
+        ];
+        for (method, parameter) in expectations {
+            usos_method(method)
+                .and(body_string_contains(parameter))
+                .and(body_string_contains("fields=type%7Cstart_time"))
+                .respond_with(json_response(json!([])))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let client = mock_authorized_client(&server);
+        let token = AccessToken {
+            token: String::from("token"),
+            secret: String::from("secret").into(),
+        };
+        let day = date!(2024 - 10 - 01);
+
+        get_user_timetable(&client, &token, day, day).await.unwrap();
+        get_student_timetable(&client, &token, day, day)
+            .await
+            .unwrap();
+        get_staff_timetable(&client, &UserId::new("42"), day, day)
+            .await
+            .unwrap();
+        get_classgroup_timetable(&client, &CourseUnitId::new("98765"), 2, day, day)
+            .await
+            .unwrap();
+        get_course_edition_timetable(
+            &client,
+            &CourseId::new("MAT001"),
+            &TermId::new("2024/25-Z"),
+            day,
+            day,
+        )
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,465 @@
+//! Analysis of timetables: overlapping activities, common free slots and travel between buildings.
+//!
+//! The functions are pure, they work on activities already fetched with the functions of the [parent module](super).
+//! Times are compared in the local Polish time, as provided by USOS API.
+
+use std::collections::HashMap;
+use time::{Date, Duration, PrimitiveDateTime, Time, Weekday};
+use usos_core::api::types::{ids::BuildingId, time::UsosDateTime};
+
+use super::Activity;
+use crate::geo::Coordinates;
+
+/// Two activities that take place at the same time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conflict<'a> {
+    /// The activity that starts first.
+    pub first: &'a Activity,
+    pub second: &'a Activity,
+}
+
+impl Conflict<'_> {
+    pub fn duration(&self) -> Duration {
+        let end = self.first.end_time.min(self.second.end_time);
+        end.0 - self.second.start_time.0
+    }
+}
+
+/// Finds all pairs of overlapping activities, ordered by the start of the earlier one.
+///
+/// Activities that end exactly when the other one starts do not overlap.
+pub fn find_conflicts(activities: &[Activity]) -> Vec<Conflict<'_>> {
+    let mut sorted = activities.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|activity| (activity.start_time, activity.end_time));
+
+    let mut conflicts = Vec::new();
+    for (i, first) in sorted.iter().enumerate() {
+        for second in sorted[i + 1..]
+            .iter()
+            .take_while(|second| second.start_time < first.end_time)
+        {
+            conflicts.push(Conflict { first, second });
+        }
+    }
+    conflicts
+}
+
+/// Part of the day within which free slots are searched, see [`find_free_slots`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingHours {
+    start: Time,
+    end: Time,
+    weekends: bool,
+}
+
+impl WorkingHours {
+    /// Working hours on weekdays, use [`WorkingHours::with_weekends`] to include Saturdays and Sundays.
+    pub fn new(start: Time, end: Time) -> Self {
+        Self {
+            start,
+            end,
+            weekends: false,
+        }
+    }
+
+    pub fn with_weekends(mut self, weekends: bool) -> Self {
+        self.weekends = weekends;
+        self
+    }
+
+    fn window(&self, date: Date) -> Option<(PrimitiveDateTime, PrimitiveDateTime)> {
+        let weekend = matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday);
+        (self.start < self.end && (self.weekends || !weekend))
+            .then(|| (date.with_time(self.start), date.with_time(self.end)))
+    }
+}
+
+impl Default for WorkingHours {
+    /// 8:00 to 20:00 on weekdays.
+    fn default() -> Self {
+        Self::new(
+            Time::from_hms(8, 0, 0).unwrap(),
+            Time::from_hms(20, 0, 0).unwrap(),
+        )
+    }
+}
+
+/// Time not taken by any activity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FreeSlot {
+    pub start: UsosDateTime,
+    pub end: UsosDateTime,
+}
+
+impl FreeSlot {
+    pub fn duration(&self) -> Duration {
+        self.end.0 - self.start.0
+    }
+}
+
+/// Finds the slots within the working hours of the days from `start` to `end` (both included) in which none of the
+/// timetables has any activity, e.g. of all members of a council or all class groups of a year.
+///
+/// Slots shorter than `min_duration` are skipped. Slots are ordered by their start.
+pub fn find_free_slots<'a>(
+    timetables: impl IntoIterator<Item = &'a [Activity]>,
+    start: Date,
+    end: Date,
+    hours: &WorkingHours,
+    min_duration: Duration,
+) -> Vec<FreeSlot> {
+    let mut busy = timetables
+        .into_iter()
+        .flatten()
+        .map(|activity| (activity.start_time.0, activity.end_time.0))
+        .collect::<Vec<_>>();
+    busy.sort();
+
+    let mut slots = Vec::new();
+    let mut date = start;
+    while date <= end {
+        if let Some((window_start, window_end)) = hours.window(date) {
+            let mut free_from = window_start;
+            for &(busy_start, busy_end) in busy.iter().filter(|(busy_start, busy_end)| {
+                *busy_end > window_start && *busy_start < window_end
+            }) {
+                if busy_start > free_from {
+                    slots.push((free_from, busy_start));
+                }
+                free_from = free_from.max(busy_end);
+            }
+            if window_end > free_from {
+                slots.push((free_from, window_end));
+            }
+        }
+        let Some(next) = date.next_day() else {
+            break;
+        };
+        date = next;
+    }
+
+    slots
+        .into_iter()
+        .filter(|(start, end)| *end - *start >= min_duration)
+        .map(|(start, end)| FreeSlot {
+            start: UsosDateTime(start),
+            end: UsosDateTime(end),
+        })
+        .collect()
+}
+
+/// Travel speed in meters per second, finite and positive.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Speed(f64);
+
+impl Speed {
+    /// Average walking speed.
+    pub const WALKING: Speed = Speed(1.25);
+
+    pub fn meters_per_second(self) -> f64 {
+        self.0
+    }
+}
+
+impl TryFrom<f64> for Speed {
+    type Error = String;
+
+    fn try_from(meters_per_second: f64) -> Result<Self, Self::Error> {
+        if meters_per_second.is_finite() && meters_per_second > 0.0 {
+            Ok(Self(meters_per_second))
+        } else {
+            Err(format!(
+                "Speed has to be finite and positive, got {meters_per_second}"
+            ))
+        }
+    }
+}
+
+/// Consecutive activities in different buildings with too little time to get from one to the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TravelIssue<'a> {
+    pub from: &'a Activity,
+    pub to: &'a Activity,
+    /// Distance between the buildings in meters.
+    pub distance: f64,
+    /// Time between the end of `from` and the start of `to`.
+    pub available: Duration,
+    /// Estimated time of getting from one building to the other.
+    pub required: Duration,
+}
+
+/// Checks whether there is enough time to walk between consecutive activities, see [`TravelCheck::check`].
+#[derive(Debug, Clone)]
+pub struct TravelCheck<'a> {
+    buildings: &'a HashMap<BuildingId, Coordinates>,
+    speed: Speed,
+    margin: Duration,
+}
+
+impl<'a> TravelCheck<'a> {
+    /// Uses the locations of the buildings, usually fetched from `geo/building2` with
+    /// [`get_activity_locations`](crate::geo::get_activity_locations).
+    pub fn new(buildings: &'a HashMap<BuildingId, Coordinates>) -> Self {
+        Self {
+            buildings,
+            speed: Speed::WALKING,
+            margin: Duration::ZERO,
+        }
+    }
+
+    /// Sets the travel speed, [`Speed::WALKING`] by default.
+    pub fn with_speed(mut self, speed: Speed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Adds time needed regardless of the distance, e.g. to leave a room, none by default.
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Estimated time of getting between the buildings, [`None`] if the coordinates of any of them are unknown.
+    pub fn travel_time(&self, from: &BuildingId, to: &BuildingId) -> Option<(f64, Duration)> {
+        if from == to {
+            return Some((0.0, Duration::ZERO));
+        }
+        let distance = self
+            .buildings
+            .get(from)?
+            .distance_to(self.buildings.get(to)?);
+        Some((
+            distance,
+            // saturating, as a very low speed gives a travel time longer than any duration
+            Duration::saturating_seconds_f64(distance / self.speed.meters_per_second())
+                .saturating_add(self.margin),
+        ))
+    }
+
+    /// Finds consecutive activities of the same day that take place in different buildings and leave too little time
+    /// to get between them.
+    ///
+    /// Overlapping activities are reported by [`find_conflicts`] instead, and activities without a building or in
+    /// buildings with unknown coordinates are skipped.
+    pub fn check<'b>(&self, activities: &'b [Activity]) -> Vec<TravelIssue<'b>> {
+        let mut sorted = activities.iter().collect::<Vec<_>>();
+        sorted.sort_by_key(|activity| (activity.start_time, activity.end_time));
+
+        // the travel starts after the activity that ends last, as a shorter one may be nested inside it
+        let mut latest: Option<&Activity> = None;
+        sorted
+            .into_iter()
+            .filter_map(|to| {
+                let from = latest;
+                if latest.is_none_or(|latest| to.end_time > latest.end_time) {
+                    latest = Some(to);
+                }
+                self.issue(from?, to)
+            })
+            .collect()
+    }
+
+    fn issue<'b>(&self, from: &'b Activity, to: &'b Activity) -> Option<TravelIssue<'b>> {
+        let available = to.start_time.0 - from.end_time.0;
+        if available.is_negative() || from.end_time.0.date() != to.start_time.0.date() {
+            return None;
+        }
+        let (distance, required) =
+            self.travel_time(from.building_id.as_ref()?, to.building_id.as_ref()?)?;
+        (required > available).then_some(TravelIssue {
+            from,
+            to,
+            distance,
+            available,
+            required,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use time::macros::{date, datetime, time};
+
+    use super::*;
+    use crate::tt::tests::lecture;
+
+    fn activity(unit_id: u64, start: &str, end: &str, building: &str) -> Activity {
+        let mut json = lecture(unit_id, start, end);
+        json["building_id"] = building.into();
+        Activity::deserialize(json).unwrap()
+    }
+
+    fn units(activities: impl IntoIterator<Item = (u64, u64)>) -> Vec<(String, String)> {
+        activities
+            .into_iter()
+            .map(|(first, second)| (first.to_string(), second.to_string()))
+            .collect()
+    }
+
+    fn conflict_units(conflicts: &[Conflict]) -> Vec<(String, String)> {
+        conflicts
+            .iter()
+            .map(|conflict| {
+                (
+                    conflict.first.unit_id.as_ref().unwrap().to_string(),
+                    conflict.second.unit_id.as_ref().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_activities_are_found() {
+        let activities = [
+            activity(3, "2024-10-01 10:00:00", "2024-10-01 11:00:00", "C-13"),
+            activity(1, "2024-10-01 07:30:00", "2024-10-01 09:00:00", "C-13"),
+            activity(2, "2024-10-01 08:00:00", "2024-10-01 10:30:00", "C-13"),
+            activity(4, "2024-10-01 11:00:00", "2024-10-01 12:00:00", "C-13"),
+        ];
+
+        let conflicts = find_conflicts(&activities);
+
+        assert_eq!(conflict_units(&conflicts), units([(1, 2), (2, 3)]));
+        assert_eq!(conflicts[0].duration(), Duration::minutes(60));
+        assert_eq!(conflicts[1].duration(), Duration::minutes(30));
+    }
+
+    #[test]
+    fn common_free_slots_are_found() {
+        let first = [
+            activity(1, "2024-10-04 09:00:00", "2024-10-04 10:30:00", "C-13"),
+            activity(2, "2024-10-04 13:00:00", "2024-10-04 14:00:00", "C-13"),
+        ];
+        let second = [
+            activity(3, "2024-10-04 07:00:00", "2024-10-04 08:30:00", "C-13"),
+            activity(4, "2024-10-04 10:00:00", "2024-10-04 11:00:00", "C-13"),
+            activity(5, "2024-10-04 15:45:00", "2024-10-04 16:00:00", "C-13"),
+        ];
+
+        // from Friday to Monday, weekend excluded
+        let slots = find_free_slots(
+            [&first[..], &second[..]],
+            date!(2024 - 10 - 04),
+            date!(2024 - 10 - 07),
+            &WorkingHours::new(time!(8:00), time!(16:00)),
+            Duration::minutes(30),
+        );
+
+        assert_eq!(
+            slots
+                .iter()
+                .map(|slot| (slot.start.0, slot.end.0))
+                .collect::<Vec<_>>(),
+            [
+                (datetime!(2024-10-04 08:30), datetime!(2024-10-04 09:00)),
+                (datetime!(2024-10-04 11:00), datetime!(2024-10-04 13:00)),
+                (datetime!(2024-10-04 14:00), datetime!(2024-10-04 15:45)),
+                (datetime!(2024-10-07 08:00), datetime!(2024-10-07 16:00)),
+            ]
+        );
+        assert_eq!(slots[1].duration(), Duration::hours(2));
+    }
+
+    #[test]
+    fn weekends_can_be_included() {
+        let slots = find_free_slots(
+            [],
+            date!(2024 - 10 - 05),
+            date!(2024 - 10 - 06),
+            &WorkingHours::default().with_weekends(true),
+            Duration::ZERO,
+        );
+
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[0].start.0, datetime!(2024-10-05 08:00));
+        assert_eq!(slots[1].end.0, datetime!(2024-10-06 20:00));
+    }
+
+    #[test]
+    fn speeds_are_validated() {
+        assert_eq!(Speed::try_from(1.25), Ok(Speed::WALKING));
+        for invalid in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(Speed::try_from(invalid).is_err(), "{invalid}");
+        }
+
+        let buildings = HashMap::from([
+            (BuildingId::new("C-13"), Coordinates::new(51.1071, 17.0617)),
+            (BuildingId::new("D-1"), Coordinates::new(51.1100, 17.0320)),
+        ]);
+        let crawling =
+            TravelCheck::new(&buildings).with_speed(Speed::try_from(f64::MIN_POSITIVE).unwrap());
+        assert_eq!(
+            crawling
+                .travel_time(&BuildingId::new("C-13"), &BuildingId::new("D-1"))
+                .unwrap()
+                .1,
+            Duration::MAX
+        );
+    }
+
+    #[test]
+    fn infeasible_travels_are_found() {
+        let buildings = HashMap::from([
+            (BuildingId::new("C-13"), Coordinates::new(51.1071, 17.0617)),
+            (BuildingId::new("D-1"), Coordinates::new(51.1100, 17.0320)),
+            (BuildingId::new("A-1"), Coordinates::new(51.1072, 17.0618)),
+        ]);
+        let activities = [
+            activity(1, "2024-10-01 07:30:00", "2024-10-01 09:00:00", "C-13"),
+            // ~2 km in 15 minutes
+            activity(2, "2024-10-01 09:15:00", "2024-10-01 11:00:00", "D-1"),
+            // the same building
+            activity(3, "2024-10-01 11:00:00", "2024-10-01 12:00:00", "D-1"),
+            // ~2 km in an hour
+            activity(4, "2024-10-01 13:00:00", "2024-10-01 14:00:00", "C-13"),
+            // ~13 m without a break
+            activity(5, "2024-10-01 14:00:00", "2024-10-01 15:00:00", "A-1"),
+            // unknown building
+            activity(6, "2024-10-01 15:00:00", "2024-10-01 16:00:00", "X-9"),
+        ];
+
+        let issues = TravelCheck::new(&buildings).check(&activities);
+        let with_margin = TravelCheck::new(&buildings)
+            .with_margin(Duration::minutes(5))
+            .check(&activities);
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].from.unit_id, activities[0].unit_id);
+        assert_eq!(issues[0].to.unit_id, activities[1].unit_id);
+        assert_eq!(issues[0].available, Duration::minutes(15));
+        assert!(issues[0].required > Duration::minutes(25));
+        assert_eq!(issues[1].to.unit_id, activities[4].unit_id);
+        assert_eq!(issues[1].available, Duration::ZERO);
+        assert_eq!(with_margin.len(), 2);
+        assert_eq!(
+            with_margin[1].required - issues[1].required,
+            Duration::minutes(5)
+        );
+        assert!(TravelCheck::new(&buildings)
+            .with_speed(Speed::try_from(10.0).unwrap())
+            .check(&activities[..2])
+            .is_empty());
+    }
+
+    #[test]
+    fn travel_starts_after_the_latest_end() {
+        let buildings = HashMap::from([
+            (BuildingId::new("C-13"), Coordinates::new(51.1071, 17.0617)),
+            (BuildingId::new("D-1"), Coordinates::new(51.1100, 17.0320)),
+        ]);
+        let activities = [
+            activity(1, "2024-10-01 08:00:00", "2024-10-01 10:00:00", "C-13"),
+            // nested inside the first one
+            activity(2, "2024-10-01 08:30:00", "2024-10-01 09:00:00", "C-13"),
+            activity(3, "2024-10-01 10:05:00", "2024-10-01 11:00:00", "D-1"),
+        ];
+
+        let issues = TravelCheck::new(&buildings).check(&activities);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].from.unit_id, activities[0].unit_id);
+        assert_eq!(issues[0].to.unit_id, activities[2].unit_id);
+        assert_eq!(issues[0].available, Duration::minutes(5));
+    }
+}